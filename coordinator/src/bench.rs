@@ -0,0 +1,405 @@
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::Sender;
+use tokio::sync::Mutex;
+
+use crate::coordinator::downtime::{DowntimeReportEntry, DowntimeWindow};
+use crate::coordinator::latency::LatencySummary;
+use crate::coordinator::timeline::ElectionRecord;
+use crate::coordinator::workload::WorkloadSpec;
+use crate::coordinator::{NetworkState, NODES};
+use crate::messages::coordinator::{APIResponse, CDMessage, Role};
+use crate::messages::{ui::UIMessage, IOMessage};
+use crate::utils::{
+    get_arg_value, parse_duration, BENCH_DRAIN_TIME, BENCH_SAMPLE_RATE, BENCH_STARTUP_TIMEOUT,
+    DEFAULT_BENCH_DURATION, DEFAULT_BENCH_REPORT_FILE, SCENARIOS, SCENARIO_NODES,
+};
+
+/// Configuration of a benchmark run, given as `app bench --workload <preset or file>
+/// [--duration 60s] [--out report.json] [--scenario <name> [--scenario-at 30s]]`.
+#[derive(Debug, Clone)]
+pub struct BenchConfig {
+    pub workload: WorkloadSpec,
+    pub duration: Duration,
+    pub out: String,
+    pub scenario: Option<(String, Duration)>,
+}
+
+impl BenchConfig {
+    pub fn from_args() -> Result<Self, String> {
+        let workload_arg = get_arg_value("--workload").ok_or("missing --workload argument")?;
+        let mut workload = match WorkloadSpec::preset(&workload_arg) {
+            Some(spec) => spec,
+            None => {
+                let file = std::fs::read_to_string(&workload_arg)
+                    .map_err(|e| format!("failed to read workload file {workload_arg}: {e}"))?;
+                serde_json::from_str(&file)
+                    .map_err(|e| format!("invalid workload file {workload_arg}: {e}"))?
+            }
+        };
+        workload.validate()?;
+        let duration = match get_arg_value("--duration") {
+            Some(d) => parse_duration(&d).ok_or(format!("invalid duration {d}"))?,
+            None => DEFAULT_BENCH_DURATION,
+        };
+        // The workload runs for the whole benchmark
+        workload.ops = None;
+        workload.duration_secs = Some(duration.as_secs_f64());
+        let scenario = match get_arg_value("--scenario") {
+            Some(name) => {
+                // Fail now rather than halfway through the run
+                if !SCENARIOS.contains(&name.as_str()) {
+                    return Err(format!(
+                        "unknown scenario {name}, expected one of {}",
+                        SCENARIOS.join(", ")
+                    ));
+                }
+                if NODES.len() != SCENARIO_NODES {
+                    return Err(format!(
+                        "scenarios need {SCENARIO_NODES} nodes, but NODES has {}",
+                        NODES.len()
+                    ));
+                }
+                let at = match get_arg_value("--scenario-at") {
+                    Some(at) => parse_duration(&at).ok_or(format!("invalid scenario time {at}"))?,
+                    None => duration / 2,
+                };
+                Some((name, at))
+            }
+            None => None,
+        };
+        let out = get_arg_value("--out").unwrap_or(DEFAULT_BENCH_REPORT_FILE.to_string());
+        Ok(Self {
+            workload,
+            duration,
+            out,
+            scenario,
+        })
+    }
+}
+
+/// Throughput and latency during one sample period of the benchmark.
+struct Sample {
+    elapsed: f64,
+    decided_idx: u64,
+    throughput: f64,
+    /// Worst latencies among the coordinator's latency reports during the period.
+    p50: Option<Duration>,
+    p99: Option<Duration>,
+}
+
+#[derive(Default)]
+pub struct BenchResults {
+    decided_idx: u64,
+    network_state: NetworkState,
+    elections: Vec<ElectionRecord>,
+    downtime: Vec<DowntimeWindow>,
+    latency: LatencySummary,
+    sample_p50: Option<Duration>,
+    sample_p99: Option<Duration>,
+    issued_ops: Option<u64>,
+    failed_proposals: u64,
+    retried_proposals: u64,
+}
+
+/// Takes the place of the TUI in benchmark mode and records what the coordinator reports.
+pub struct BenchRecorder {
+    results: Arc<Mutex<BenchResults>>,
+}
+
+impl BenchRecorder {
+    pub fn new(results: Arc<Mutex<BenchResults>>) -> Self {
+        Self { results }
+    }
+
+    pub(crate) async fn handle(&mut self, m: UIMessage) {
+        let mut results = self.results.lock().await;
+        match m {
+            UIMessage::OmnipaxosResponse(APIResponse::Decided(idx), _) => {
+                results.decided_idx = results.decided_idx.max(idx);
+            }
+            UIMessage::OmnipaxosNetworkUpdate(network_state) => {
+                results.network_state = network_state;
+            }
+            UIMessage::ElectionTimeline(records) => results.elections = records,
+            UIMessage::Downtime(window) => results.downtime.push(window),
+            UIMessage::Latency(report) => {
+                results.latency = report.overall;
+                if let Some(interval) = report.interval {
+                    results.sample_p50 = results.sample_p50.max(Some(interval.p50));
+                    results.sample_p99 = results.sample_p99.max(Some(interval.p99));
+                }
+            }
+            UIMessage::WorkloadFinished(ops) => results.issued_ops = Some(ops),
+            UIMessage::ProposalsFailed(failures) => {
+                results.failed_proposals += failures.len() as u64;
+            }
+            UIMessage::ProposalsRetried(count) => results.retried_proposals += count as u64,
+            UIMessage::OmnipaxosNodeCrashed(pid) => println!("Node {pid} crashed"),
+            UIMessage::ClusterUnreachable => println!("Cluster unreachable, holding proposals"),
+            _ => (),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ThroughputReport {
+    decided: u64,
+    avg_ops: f64,
+    max_ops: f64,
+}
+
+#[derive(Serialize)]
+struct LatencyReport {
+    count: u64,
+    p50_ms: f64,
+    p95_ms: f64,
+    p99_ms: f64,
+    max_ms: f64,
+}
+
+#[derive(Serialize)]
+struct FailoverReport {
+    time: String,
+    round: u32,
+    leader: u64,
+    old_leader: Option<u64>,
+    unavailability_ms: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct NodeReport {
+    pid: u64,
+    role: Option<Role>,
+    accepted_idx: Option<u64>,
+    decided_idx: Option<u64>,
+    compacted_idx: Option<u64>,
+    log_size: Option<u64>,
+    alive: bool,
+}
+
+#[derive(Serialize)]
+struct BenchReport {
+    workload: WorkloadSpec,
+    duration_s: f64,
+    scenario: Option<String>,
+    scenario_at_s: Option<f64>,
+    issued_ops: Option<u64>,
+    failed_proposals: u64,
+    retried_proposals: u64,
+    throughput: ThroughputReport,
+    latency: LatencyReport,
+    failovers: Vec<FailoverReport>,
+    downtime: Vec<DowntimeReportEntry>,
+    nodes: Vec<NodeReport>,
+}
+
+fn as_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Drives a benchmark: waits for a leader, runs the workload and the optional scenario, and
+/// writes the report before exiting.
+pub struct BenchRunner {
+    config: BenchConfig,
+    io_sender: Sender<IOMessage>,
+    results: Arc<Mutex<BenchResults>>,
+    samples: Vec<Sample>,
+}
+
+impl BenchRunner {
+    pub fn new(
+        config: BenchConfig,
+        io_sender: Sender<IOMessage>,
+        results: Arc<Mutex<BenchResults>>,
+    ) -> Self {
+        Self {
+            config,
+            io_sender,
+            results,
+            samples: vec![],
+        }
+    }
+
+    async fn send(&self, m: CDMessage) {
+        self.io_sender.send(IOMessage::CDMessage(m)).await.unwrap();
+    }
+
+    async fn wait_for_leader(&self) -> Result<(), String> {
+        let start = Instant::now();
+        let mut interval = tokio::time::interval(BENCH_SAMPLE_RATE / 10);
+        loop {
+            interval.tick().await;
+            if self.results.lock().await.network_state.max_round.is_some() {
+                return Ok(());
+            }
+            if start.elapsed() >= BENCH_STARTUP_TIMEOUT {
+                return Err(format!(
+                    "no leader elected within {}s",
+                    BENCH_STARTUP_TIMEOUT.as_secs()
+                ));
+            }
+        }
+    }
+
+    async fn take_sample(&mut self, elapsed: f64, last_idx: u64, last_elapsed: f64) -> u64 {
+        let mut results = self.results.lock().await;
+        let decided_idx = results.decided_idx;
+        let throughput =
+            (decided_idx - last_idx) as f64 / (elapsed - last_elapsed).max(f64::EPSILON);
+        let sample = Sample {
+            elapsed,
+            decided_idx,
+            throughput,
+            p50: results.sample_p50.take(),
+            p99: results.sample_p99.take(),
+        };
+        println!(
+            "[{:>6.1}s] decided {:>8} ({:>8.0} ops/s), p99 {}",
+            sample.elapsed,
+            sample.decided_idx,
+            sample.throughput,
+            sample
+                .p99
+                .map_or("N/A".to_string(), |p99| format!("{:.2} ms", as_ms(p99)))
+        );
+        self.samples.push(sample);
+        decided_idx
+    }
+
+    pub async fn run(&mut self) {
+        if let Err(e) = self.wait_for_leader().await {
+            eprintln!("Benchmark failed: {e}");
+            std::process::exit(1);
+        }
+        println!("Running workload: {}", self.config.workload.describe());
+        let start_idx = self.results.lock().await.decided_idx;
+        self.send(CDMessage::StartWorkload(self.config.workload.clone()))
+            .await;
+        let start = Instant::now();
+        let mut scenario = self.config.scenario.clone();
+        let mut sample_interval = tokio::time::interval(BENCH_SAMPLE_RATE);
+        sample_interval.tick().await;
+        let (mut last_idx, mut last_elapsed) = (start_idx, 0.0);
+        while start.elapsed() < self.config.duration + BENCH_DRAIN_TIME {
+            sample_interval.tick().await;
+            let elapsed = start.elapsed();
+            if matches!(&scenario, Some((_, at)) if *at <= elapsed) {
+                let (name, _) = scenario.take().unwrap();
+                println!("Running scenario {name}");
+                self.send(CDMessage::Scenario(name)).await;
+            }
+            let elapsed = elapsed.as_secs_f64();
+            last_idx = self.take_sample(elapsed, last_idx, last_elapsed).await;
+            last_elapsed = elapsed;
+        }
+        self.send(CDMessage::StopWorkload).await;
+        match self.write_report(start, start_idx).await {
+            Ok(csv_file) => println!("Wrote report to {} and {csv_file}", self.config.out),
+            Err(e) => {
+                eprintln!("Failed to write report: {e}");
+                std::process::exit(1);
+            }
+        }
+        self.io_sender
+            .send(IOMessage::UIMessage(UIMessage::Exit))
+            .await
+            .unwrap();
+    }
+
+    /// Writes the JSON report and the per-sample CSV next to it. Returns the CSV file name.
+    async fn write_report(&self, start: Instant, start_idx: u64) -> std::io::Result<String> {
+        let results = self.results.lock().await;
+        let decided = results.decided_idx - start_idx;
+        let duration_s = self.config.duration.as_secs_f64();
+        // Includes the time spent waiting for the last proposals to be decided
+        let measured_s = self.samples.last().map_or(duration_s, |s| s.elapsed);
+        let failovers = results
+            .elections
+            .iter()
+            .filter(|record| record.changed_at >= start)
+            .map(|record| FailoverReport {
+                time: record.time.to_rfc3339(),
+                round: record.round.round_num,
+                leader: record.round.leader,
+                old_leader: record.old_leader,
+                unavailability_ms: record.unavailability.map(as_ms),
+            })
+            .collect();
+        let node_status = &results.network_state.node_status;
+        let nodes = results
+            .network_state
+            .nodes
+            .iter()
+            .map(|pid| {
+                let status = node_status.get(pid);
+                NodeReport {
+                    pid: *pid,
+                    role: status.map(|s| s.role),
+                    accepted_idx: status.map(|s| s.accepted_idx),
+                    decided_idx: status.map(|s| s.decided_idx),
+                    compacted_idx: status.map(|s| s.compacted_idx),
+                    log_size: status.map(|s| s.log_size),
+                    alive: results.network_state.alive_nodes.contains(pid),
+                }
+            })
+            .collect();
+        let report = BenchReport {
+            workload: self.config.workload.clone(),
+            duration_s,
+            scenario: self.config.scenario.as_ref().map(|(name, _)| name.clone()),
+            scenario_at_s: self
+                .config
+                .scenario
+                .as_ref()
+                .map(|(_, at)| at.as_secs_f64()),
+            issued_ops: results.issued_ops,
+            failed_proposals: results.failed_proposals,
+            retried_proposals: results.retried_proposals,
+            throughput: ThroughputReport {
+                decided,
+                avg_ops: decided as f64 / measured_s,
+                max_ops: self
+                    .samples
+                    .iter()
+                    .map(|s| s.throughput)
+                    .fold(0.0, f64::max),
+            },
+            latency: LatencyReport {
+                count: results.latency.count,
+                p50_ms: as_ms(results.latency.p50),
+                p95_ms: as_ms(results.latency.p95),
+                p99_ms: as_ms(results.latency.p99),
+                max_ms: as_ms(results.latency.max),
+            },
+            failovers,
+            downtime: results.downtime.iter().map(|w| w.into()).collect(),
+            nodes,
+        };
+        let json = serde_json::to_string_pretty(&report)?;
+        std::fs::write(&self.config.out, json)?;
+
+        let csv_file = match self.config.out.strip_suffix(".json") {
+            Some(base) => format!("{base}.csv"),
+            None => format!("{}.csv", self.config.out),
+        };
+        let mut csv = "elapsed_s,decided_idx,throughput_ops,p50_ms,p99_ms\n".to_string();
+        let ms_or_empty =
+            |d: Option<Duration>| d.map_or(String::new(), |d| format!("{:.3}", as_ms(d)));
+        for s in &self.samples {
+            let _ = writeln!(
+                csv,
+                "{:.3},{},{:.1},{},{}",
+                s.elapsed,
+                s.decided_idx,
+                s.throughput,
+                ms_or_empty(s.p50),
+                ms_or_empty(s.p99)
+            );
+        }
+        std::fs::write(&csv_file, csv)?;
+        Ok(csv_file)
+    }
+}
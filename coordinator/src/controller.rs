@@ -1,18 +1,31 @@
+use crate::bench::BenchRecorder;
 use crate::messages::coordinator::CDMessage;
+use crate::messages::ui::UIMessage;
 use crate::messages::IOMessage;
 use crate::ui::UI;
 use tokio::sync::mpsc::{Receiver, Sender};
 
+/// Where the messages for the user end up.
+pub enum Frontend {
+    Tui(UI),
+    /// Benchmark mode without a terminal UI.
+    Bench(BenchRecorder),
+}
+
 pub struct Controller {
-    ui: UI,
+    frontend: Frontend,
     io_receiver: Receiver<IOMessage>,
     cd_sender: Sender<CDMessage>,
 }
 
 impl Controller {
-    pub fn new(ui: UI, io_receiver: Receiver<IOMessage>, cd_sender: Sender<CDMessage>) -> Self {
+    pub fn new(
+        frontend: Frontend,
+        io_receiver: Receiver<IOMessage>,
+        cd_sender: Sender<CDMessage>,
+    ) -> Self {
         Self {
-            ui,
+            frontend,
             io_receiver,
             cd_sender,
         }
@@ -23,15 +36,21 @@ impl Controller {
             IOMessage::CDMessage(cd_m) => {
                 self.cd_sender.send(cd_m).await.unwrap();
             }
-            IOMessage::UIMessage(ui_m) => {
-                self.ui.handle(ui_m).await;
-            }
+            IOMessage::UIMessage(ui_m) => match &mut self.frontend {
+                Frontend::Tui(ui) => ui.handle(ui_m).await,
+                Frontend::Bench(recorder) => recorder.handle(ui_m).await,
+            },
         }
     }
 
+    /// Forwards messages until the frontend has handled an exit request.
     pub async fn run(&mut self) {
         while let Some(m) = self.io_receiver.recv().await {
+            let exit = matches!(m, IOMessage::UIMessage(UIMessage::Exit));
             self.handle(m).await;
+            if exit {
+                break;
+            }
         }
     }
 }
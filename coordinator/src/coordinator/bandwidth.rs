@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use crate::utils::BANDWIDTH_BURST_WINDOW;
+
+/// Limits the bytes per second sent over a link. Up to `BANDWIDTH_BURST_WINDOW` worth of bytes
+/// can be sent at once after the link was idle.
+#[derive(Debug)]
+pub struct TokenBucket {
+    /// Bytes per second.
+    rate: u64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(rate: u64) -> Self {
+        let mut bucket = Self {
+            rate,
+            tokens: 0.0,
+            last_refill: Instant::now(),
+        };
+        bucket.tokens = bucket.capacity();
+        bucket
+    }
+
+    pub fn rate(&self) -> u64 {
+        self.rate
+    }
+
+    fn capacity(&self) -> f64 {
+        (self.rate as f64 * BANDWIDTH_BURST_WINDOW.as_secs_f64()).max(1.0)
+    }
+
+    /// Takes the tokens for a message of `bytes` and returns how long it must wait for them. The
+    /// bucket goes into debt for messages that don't fit, so that the queued messages leave in
+    /// order at the configured rate.
+    pub fn reserve(&mut self, bytes: usize) -> Duration {
+        let now = Instant::now();
+        let refill = now.duration_since(self.last_refill).as_secs_f64() * self.rate as f64;
+        self.tokens = (self.tokens + refill).min(self.capacity()) - bytes as f64;
+        self.last_refill = now;
+        match self.tokens < 0.0 {
+            true => Duration::from_secs_f64(-self.tokens / self.rate as f64),
+            false => Duration::ZERO,
+        }
+    }
+}
+
+/// Messages waiting to be delivered over a link, because of a delay or a bandwidth limit.
+#[derive(Debug, Default)]
+pub struct LinkQueue {
+    pub bucket: Option<TokenBucket>,
+    pub messages: usize,
+    pub bytes: usize,
+}
+
+/// Queues of the links, by the port of the sending node.
+pub type LinkQueues = Arc<Mutex<HashMap<u64, LinkQueue>>>;
+
+#[derive(Debug, Clone, Copy)]
+pub struct LinkQueueStatus {
+    pub from: u64,
+    pub to: u64,
+    /// Bytes per second.
+    pub bandwidth: u64,
+    pub messages: usize,
+    pub bytes: usize,
+}
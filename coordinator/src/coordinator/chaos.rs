@@ -0,0 +1,156 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::Mutex;
+
+use crate::messages::coordinator::{CDMessage, Round};
+use crate::messages::IOMessage;
+use crate::utils::{CHAOS_MAX_LINK_DELAY, CHAOS_MIN_LINK_DELAY};
+
+/// Settings of a chaos run. Runs with the same seed and nodes inject the same sequence of faults.
+#[derive(Debug, Clone, Copy)]
+pub struct ChaosConfig {
+    pub seed: u64,
+    /// Time between two faults.
+    pub interval: Duration,
+}
+
+/// A fault injected by the nemesis. Partitioning actions replace the links cut before, delays
+/// stay until the network is healed.
+#[derive(Debug, Clone)]
+pub enum ChaosAction {
+    /// Cut all links between the two sides.
+    Partition(Vec<u64>, Vec<u64>),
+    /// Cut all links of the leader.
+    IsolateLeader(u64),
+    /// Cut all links between `left` and `right`, which can only talk through `bridge`.
+    Bridge {
+        bridge: u64,
+        left: Vec<u64>,
+        right: Vec<u64>,
+    },
+    /// Delay the messages between two nodes, in both directions.
+    Delay(u64, u64, Duration),
+    /// Restore all links and remove all delays.
+    Heal,
+}
+
+impl fmt::Display for ChaosAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let group = |nodes: &[u64]| {
+            let nodes: Vec<String> = nodes.iter().map(|n| n.to_string()).collect();
+            format!("{{{}}}", nodes.join(","))
+        };
+        match self {
+            ChaosAction::Partition(a, b) => write!(f, "partition {} {}", group(a), group(b)),
+            ChaosAction::IsolateLeader(leader) => write!(f, "isolate leader {leader}"),
+            ChaosAction::Bridge {
+                bridge,
+                left,
+                right,
+            } => write!(
+                f,
+                "bridge {} <-> {bridge} <-> {}",
+                group(left),
+                group(right)
+            ),
+            ChaosAction::Delay(a, b, delay) => {
+                write!(f, "delay {a} <-> {b} by {}ms", delay.as_millis())
+            }
+            ChaosAction::Heal => write!(f, "heal"),
+        }
+    }
+}
+
+/// Picks faults at random from a seeded RNG.
+pub struct Nemesis {
+    rng: StdRng,
+    nodes: Vec<u64>,
+}
+
+impl Nemesis {
+    pub fn new(seed: u64, nodes: Vec<u64>) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            nodes,
+        }
+    }
+
+    /// Picks the next fault. Every call draws the same random numbers whatever the leader is, so
+    /// a seed always gives the same sequence of choices. Only the node isolated by
+    /// `IsolateLeader` depends on the run, since it is the leader at the time.
+    pub fn next_action(&mut self, leader: Option<u64>) -> ChaosAction {
+        let mut nodes = self.nodes.clone();
+        nodes.shuffle(&mut self.rng);
+        let kind = self.rng.gen_range(0..6);
+        let split = self.rng.gen_range(1..nodes.len().max(2));
+        let delay = self
+            .rng
+            .gen_range(CHAOS_MIN_LINK_DELAY..=CHAOS_MAX_LINK_DELAY);
+        let sorted = |nodes: &[u64]| {
+            let mut nodes = nodes.to_vec();
+            nodes.sort();
+            nodes
+        };
+        match kind {
+            0 => ChaosAction::Partition(sorted(&nodes[..split]), sorted(&nodes[split..])),
+            1 => ChaosAction::IsolateLeader(leader.unwrap_or(nodes[0])),
+            2 if nodes.len() >= 3 => {
+                let others = &nodes[1..];
+                let (left, right) = others.split_at(others.len() / 2);
+                ChaosAction::Bridge {
+                    bridge: nodes[0],
+                    left: sorted(left),
+                    right: sorted(right),
+                }
+            }
+            3 if nodes.len() >= 2 => ChaosAction::Delay(nodes[0], nodes[1], delay),
+            _ => ChaosAction::Heal,
+        }
+    }
+}
+
+/// Asks the coordinator to inject a fault picked by the nemesis every interval.
+pub struct ChaosRunner {
+    io_sender: Sender<IOMessage>,
+    max_round: Arc<Mutex<Option<Round>>>,
+    nemesis: Nemesis,
+    interval: Duration,
+}
+
+impl ChaosRunner {
+    pub fn new(
+        config: ChaosConfig,
+        nodes: Vec<u64>,
+        io_sender: Sender<IOMessage>,
+        max_round: Arc<Mutex<Option<Round>>>,
+    ) -> Self {
+        Self {
+            io_sender,
+            max_round,
+            nemesis: Nemesis::new(config.seed, nodes),
+            interval: config.interval,
+        }
+    }
+
+    pub async fn run(&mut self) {
+        let mut interval = tokio::time::interval(self.interval);
+        // The first tick completes immediately
+        interval.tick().await;
+        let mut step = 0;
+        loop {
+            interval.tick().await;
+            step += 1;
+            let leader = self.max_round.lock().await.map(|round| round.leader);
+            let action = self.nemesis.next_action(leader);
+            self.io_sender
+                .send(IOMessage::CDMessage(CDMessage::ApplyChaos(step, action)))
+                .await
+                .unwrap();
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+use crate::coordinator::timeline::ElectionRecord;
+use crate::messages::coordinator::Round;
+use crate::utils::DOWNTIME_THRESHOLD;
+
+/// A period in which nothing was decided although proposals were pending.
+#[derive(Debug, Clone)]
+pub struct DowntimeWindow {
+    pub start: DateTime<Local>,
+    pub duration: Duration,
+    /// The election that happened during the window and after which decisions resumed.
+    pub ended_by: Option<Round>,
+    /// Writes first sent to the cluster during the window.
+    pub proposed: u64,
+    /// Writes sent to the cluster during the window, including retries.
+    pub attempts: u64,
+    /// Entries decided during the window, including the ones that ended it.
+    pub decided: u64,
+}
+
+#[derive(Serialize)]
+pub(crate) struct DowntimeReportEntry {
+    start: String,
+    duration_ms: f64,
+    ended_by_round: Option<u32>,
+    ended_by_leader: Option<u64>,
+    proposed: u64,
+    attempts: u64,
+    decided: u64,
+}
+
+impl From<&DowntimeWindow> for DowntimeReportEntry {
+    fn from(window: &DowntimeWindow) -> Self {
+        Self {
+            start: window.start.to_rfc3339(),
+            duration_ms: window.duration.as_secs_f64() * 1000.0,
+            ended_by_round: window.ended_by.map(|r| r.round_num),
+            ended_by_leader: window.ended_by.map(|r| r.leader),
+            proposed: window.proposed,
+            attempts: window.attempts,
+            decided: window.decided,
+        }
+    }
+}
+
+struct OpenWindow {
+    start: Instant,
+    start_time: DateTime<Local>,
+    proposed_at_start: u64,
+    attempts_at_start: u64,
+    decided_at_start: u64,
+}
+
+/// Detects windows with zero decided throughput while proposals are pending.
+pub struct DowntimeMonitor {
+    current: Option<OpenWindow>,
+    windows: Vec<DowntimeWindow>,
+    last_decided_idx: u64,
+    last_progress: Instant,
+    proposed_at_last_progress: u64,
+    attempts_at_last_progress: u64,
+    report_file: String,
+}
+
+impl DowntimeMonitor {
+    pub fn new(report_file: String) -> Self {
+        Self {
+            current: None,
+            windows: vec![],
+            last_decided_idx: 0,
+            last_progress: Instant::now(),
+            proposed_at_last_progress: 0,
+            attempts_at_last_progress: 0,
+            report_file,
+        }
+    }
+
+    /// Checks the current progress of the cluster. `proposed` counts the writes sent so far and
+    /// `attempts` their sends including retries. Returns the downtime window that just ended, if
+    /// any.
+    pub fn check(
+        &mut self,
+        decided_idx: u64,
+        proposed: u64,
+        attempts: u64,
+        pending: bool,
+        elections: &[ElectionRecord],
+    ) -> Option<DowntimeWindow> {
+        let now = Instant::now();
+        let made_progress = decided_idx > self.last_decided_idx;
+        let ended_window = match self.current.take() {
+            Some(window) if made_progress || !pending => {
+                let ended_by = elections
+                    .iter()
+                    .rev()
+                    .find(|record| record.changed_at >= window.start)
+                    .map(|record| record.round);
+                let ended_window = DowntimeWindow {
+                    start: window.start_time,
+                    duration: now - window.start,
+                    ended_by,
+                    proposed: proposed - window.proposed_at_start,
+                    attempts: attempts - window.attempts_at_start,
+                    decided: decided_idx - window.decided_at_start,
+                };
+                self.windows.push(ended_window.clone());
+                Some(ended_window)
+            }
+            window => {
+                self.current = window;
+                None
+            }
+        };
+        if made_progress || !pending {
+            // Idle time without pending proposals is not downtime
+            self.last_decided_idx = decided_idx;
+            self.last_progress = now;
+            self.proposed_at_last_progress = proposed;
+            self.attempts_at_last_progress = attempts;
+        } else if self.current.is_none() && now - self.last_progress >= DOWNTIME_THRESHOLD {
+            let since_progress = chrono::Duration::from_std(now - self.last_progress).unwrap();
+            self.current = Some(OpenWindow {
+                start: self.last_progress,
+                start_time: Local::now() - since_progress,
+                proposed_at_start: self.proposed_at_last_progress,
+                attempts_at_start: self.attempts_at_last_progress,
+                decided_at_start: self.last_decided_idx,
+            });
+        }
+        ended_window
+    }
+
+    /// Writes all downtime windows observed so far to the JSON report file.
+    pub fn write_report(&self) -> std::io::Result<()> {
+        let entries: Vec<DowntimeReportEntry> = self.windows.iter().map(|w| w.into()).collect();
+        let report = serde_json::to_string_pretty(&entries)?;
+        std::fs::write(&self.report_file, report)
+    }
+
+    pub fn report_file(&self) -> &str {
+        &self.report_file
+    }
+}
@@ -0,0 +1,228 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::time::Instant;
+
+use crate::coordinator::inspector::MessageKind;
+
+/// Message types a filter rule applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KindSelector {
+    Any,
+    /// BLE heartbeat requests and replies.
+    Ble,
+    /// All SequencePaxos messages.
+    Paxos,
+    Kind(MessageKind),
+}
+
+impl KindSelector {
+    fn matches(&self, kind: MessageKind) -> bool {
+        match self {
+            KindSelector::Any => true,
+            KindSelector::Ble => matches!(
+                kind,
+                MessageKind::HeartbeatRequest | MessageKind::HeartbeatReply
+            ),
+            KindSelector::Paxos => !matches!(
+                kind,
+                MessageKind::HeartbeatRequest | MessageKind::HeartbeatReply | MessageKind::Unknown
+            ),
+            KindSelector::Kind(k) => *k == kind,
+        }
+    }
+}
+
+impl FromStr for KindSelector {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let kind = match s.to_lowercase().as_str() {
+            "*" | "all" => return Ok(KindSelector::Any),
+            "ble" | "heartbeat" => return Ok(KindSelector::Ble),
+            "paxos" => return Ok(KindSelector::Paxos),
+            "heartbeatrequest" => MessageKind::HeartbeatRequest,
+            "heartbeatreply" => MessageKind::HeartbeatReply,
+            "preparereq" => MessageKind::PrepareReq,
+            "prepare" => MessageKind::Prepare,
+            "promise" => MessageKind::Promise,
+            "acceptsync" => MessageKind::AcceptSync,
+            "acceptdecide" => MessageKind::AcceptDecide,
+            "accepted" => MessageKind::Accepted,
+            "notaccepted" => MessageKind::NotAccepted,
+            "decide" => MessageKind::Decide,
+            "proposalforward" => MessageKind::ProposalForward,
+            "compaction" => MessageKind::Compaction,
+            "stopsign" => MessageKind::StopSign,
+            _ => return Err(()),
+        };
+        Ok(KindSelector::Kind(kind))
+    }
+}
+
+impl fmt::Display for KindSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KindSelector::Any => write!(f, "*"),
+            KindSelector::Ble => write!(f, "ble"),
+            KindSelector::Paxos => write!(f, "paxos"),
+            KindSelector::Kind(kind) => write!(f, "{kind:?}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterAction {
+    Drop,
+    Delay(Duration),
+}
+
+impl fmt::Display for FilterAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterAction::Drop => write!(f, "drop"),
+            FilterAction::Delay(delay) => write!(f, "delay {}ms", delay.as_millis()),
+        }
+    }
+}
+
+/// Drops or delays the messages of some types sent over some links. `None` ends match any node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FilterRule {
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+    pub kinds: KindSelector,
+    pub action: FilterAction,
+}
+
+impl FilterRule {
+    fn matches(&self, from: u64, to: u64, kind: MessageKind) -> bool {
+        !matches!(self.from, Some(f) if f != from)
+            && !matches!(self.to, Some(t) if t != to)
+            && self.kinds.matches(kind)
+    }
+}
+
+impl fmt::Display for FilterRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let end = |pid: Option<u64>| pid.map_or("*".to_string(), |p| p.to_string());
+        write!(
+            f,
+            "{}->{} {} {}",
+            end(self.from),
+            end(self.to),
+            self.kinds,
+            self.action
+        )
+    }
+}
+
+/// Parses a directed link like `3->*`, where `*` matches any node.
+pub fn parse_link(s: &str) -> Option<(Option<u64>, Option<u64>)> {
+    let (from, to) = s.split_once("->")?;
+    let end = |pid: &str| match pid {
+        "*" => Some(None),
+        pid => pid.parse().ok().map(Some),
+    };
+    Some((end(from)?, end(to)?))
+}
+
+struct ActiveRule {
+    id: u64,
+    rule: FilterRule,
+    /// Messages the rule applied to.
+    hits: u64,
+}
+
+/// Filter rules applied by the proxy, in the order they were added.
+#[derive(Default)]
+pub struct MessageFilter {
+    rules: Vec<ActiveRule>,
+    next_id: u64,
+}
+
+impl MessageFilter {
+    pub fn add(&mut self, rule: FilterRule) -> u64 {
+        self.next_id += 1;
+        self.rules.push(ActiveRule {
+            id: self.next_id,
+            rule,
+            hits: 0,
+        });
+        self.next_id
+    }
+
+    pub fn remove(&mut self, id: u64) -> Option<FilterRule> {
+        let idx = self.rules.iter().position(|r| r.id == id)?;
+        Some(self.rules.remove(idx).rule)
+    }
+
+    pub fn clear(&mut self) {
+        self.rules.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Returns the action of the first rule matching a message, if any.
+    pub fn apply(&mut self, from: u64, to: u64, kind: MessageKind) -> Option<FilterAction> {
+        let active = self
+            .rules
+            .iter_mut()
+            .find(|r| r.rule.matches(from, to, kind))?;
+        active.hits += 1;
+        Some(active.rule.action)
+    }
+
+    /// Describes the rules with the number of messages each applied to.
+    pub fn describe(&self) -> Vec<String> {
+        self.rules
+            .iter()
+            .map(|r| format!("#{} {} ({} messages)", r.id, r.rule, r.hits))
+            .collect()
+    }
+}
+
+/// Queues `msg` on the queue of its link, with `link_delay` until it may be delivered, once
+/// `filter_delay` is over. Every delayed message has its own timer, so it doesn't hold up the
+/// messages of other types on the link.
+pub fn queue_delayed(
+    queue: &UnboundedSender<(Instant, Vec<u8>)>,
+    msg: Vec<u8>,
+    link_delay: Duration,
+    filter_delay: Duration,
+) {
+    if filter_delay.is_zero() {
+        _ = queue.send((Instant::now() + link_delay, msg));
+        return;
+    }
+    let queue = queue.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(filter_delay).await;
+        _ = queue.send((Instant::now() + link_delay, msg));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn delays_only_the_filtered_messages() {
+        let (queue, mut receiver) = mpsc::unbounded_channel();
+        let filter_delay = Duration::from_millis(200);
+        let start = Instant::now();
+        queue_delayed(&queue, b"ble".to_vec(), Duration::ZERO, filter_delay);
+        queue_delayed(&queue, b"paxos".to_vec(), Duration::ZERO, Duration::ZERO);
+
+        let (deliver_at, msg) = receiver.recv().await.unwrap();
+        assert_eq!(msg, b"paxos");
+        assert!(deliver_at < start + filter_delay);
+        let (deliver_at, msg) = receiver.recv().await.unwrap();
+        assert_eq!(msg, b"ble");
+        assert!(deliver_at >= start + filter_delay);
+    }
+}
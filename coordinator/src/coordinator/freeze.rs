@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use tokio::sync::{watch, Mutex};
+
+/// How the coordinator cuts a node off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Freeze {
+    /// All traffic to and from the node, including its client API link, is held until the node
+    /// resumes, as during a GC pause or VM freeze.
+    Paused,
+    /// Requests to the node and its responses are dropped. Its peer links are untouched.
+    ClientIsolated,
+}
+
+impl fmt::Display for Freeze {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Freeze::Paused => write!(f, "paused"),
+            Freeze::ClientIsolated => write!(f, "client isolated"),
+        }
+    }
+}
+
+/// Nodes cut off by the coordinator. The proxy tasks watch it to hold the traffic of paused
+/// nodes.
+pub type Freezes = HashMap<u64, Freeze>;
+
+pub fn is_paused(freezes: &Freezes, pid: u64) -> bool {
+    freezes.get(&pid) == Some(&Freeze::Paused)
+}
+
+/// Requests to paused nodes, written to their API socket when they resume.
+pub type HeldRequests = Arc<Mutex<HashMap<u64, Vec<u8>>>>;
+
+/// Lets the proposer hold the requests to paused nodes and drop those to isolated ones.
+pub struct RequestGate {
+    freezes: watch::Receiver<Freezes>,
+    held: HeldRequests,
+}
+
+impl RequestGate {
+    pub fn new(freezes: watch::Receiver<Freezes>, held: HeldRequests) -> Self {
+        Self { freezes, held }
+    }
+
+    pub fn freeze(&self, pid: u64) -> Option<Freeze> {
+        self.freezes.borrow().get(&pid).copied()
+    }
+
+    pub async fn hold(&self, pid: u64, data: Vec<u8>) {
+        self.held.lock().await.entry(pid).or_default().extend(data);
+    }
+}
@@ -0,0 +1,224 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::messages::coordinator::KVCommand;
+use crate::utils::HISTORY_MAX_OPS;
+
+/// How an operation of the history ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Completion {
+    /// Not acknowledged yet.
+    Pending,
+    /// Acknowledged at the given time. Reads carry the value they returned.
+    Ok { at: Duration, value: Option<String> },
+    /// Gave up on without knowing whether it took effect.
+    Info,
+}
+
+/// A client operation with its real-time bounds, measured from the start of the history.
+#[derive(Debug, Clone)]
+pub struct Operation {
+    pub id: u64,
+    /// Node the operation was first sent to.
+    pub process: u64,
+    pub cmd: KVCommand,
+    pub invoke: Duration,
+    pub completion: Completion,
+}
+
+impl Operation {
+    pub fn key(&self) -> &str {
+        match &self.cmd {
+            KVCommand::Put(kv) => &kv.key,
+            KVCommand::Delete(key) | KVCommand::Get(key) => key,
+        }
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ms = |d: &Duration| d.as_secs_f64() * 1000.0;
+        write!(f, "#{} [node {}] ", self.id, self.process)?;
+        match &self.cmd {
+            KVCommand::Put(kv) => write!(f, "put({}, {})", kv.key, kv.value)?,
+            KVCommand::Delete(key) => write!(f, "delete({key})")?,
+            KVCommand::Get(key) => write!(f, "get({key})")?,
+        }
+        match &self.completion {
+            Completion::Ok { at, value } => {
+                if let KVCommand::Get(_) = self.cmd {
+                    write!(f, " -> {value:?}")?;
+                }
+                write!(f, " {:.1}..{:.1}ms", ms(&self.invoke), ms(at))
+            }
+            Completion::Pending => write!(f, " {:.1}ms.. pending", ms(&self.invoke)),
+            Completion::Info => write!(f, " {:.1}ms.. unknown outcome", ms(&self.invoke)),
+        }
+    }
+}
+
+/// A Jepsen-style history of the operations proposed by the coordinator.
+#[derive(Debug)]
+pub struct History {
+    start: Instant,
+    /// Operations ordered by id. Ids are not contiguous after trimming.
+    operations: Vec<Operation>,
+    next_id: u64,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+            operations: vec![],
+            next_id: 0,
+        }
+    }
+}
+
+impl History {
+    /// Records the invocation of `cmd` at `process`. Returns the id of the operation.
+    pub fn invoke(&mut self, process: u64, cmd: &KVCommand) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.operations.push(Operation {
+            id,
+            process,
+            cmd: cmd.clone(),
+            invoke: self.start.elapsed(),
+            completion: Completion::Pending,
+        });
+        if self.operations.len() > HISTORY_MAX_OPS {
+            self.trim();
+        }
+        id
+    }
+
+    /// Drops up to half of the oldest operations, stopping at the first pending one. Only
+    /// operations that ended before all kept operations were invoked are dropped, so the check,
+    /// which starts every key from an unknown value, stays sound. Writes given up on may still
+    /// take effect later, so they are kept if a kept operation uses their key.
+    fn trim(&mut self) {
+        let max_cut = self.operations.len() - HISTORY_MAX_OPS / 2;
+        let mut cut = 0;
+        let mut last_end = Duration::ZERO;
+        for i in 0..max_cut {
+            match &self.operations[i].completion {
+                Completion::Pending => break,
+                Completion::Ok { at, .. } => last_end = last_end.max(*at),
+                Completion::Info => (),
+            }
+            if last_end <= self.operations[i + 1].invoke {
+                cut = i + 1;
+            }
+        }
+        let kept = self.operations.split_off(cut);
+        let kept_keys: HashSet<&str> = kept.iter().map(|op| op.key()).collect();
+        let mut operations: Vec<Operation> = std::mem::take(&mut self.operations)
+            .into_iter()
+            .filter(|op| {
+                let write = !matches!(op.cmd, KVCommand::Get(_));
+                write && op.completion == Completion::Info && kept_keys.contains(op.key())
+            })
+            .collect();
+        operations.extend(kept);
+        self.operations = operations;
+    }
+
+    fn get_mut(&mut self, id: u64) -> Option<&mut Operation> {
+        let idx = self.operations.binary_search_by_key(&id, |op| op.id).ok()?;
+        Some(&mut self.operations[idx])
+    }
+
+    pub fn complete(&mut self, id: u64, value: Option<String>) {
+        let at = self.start.elapsed();
+        if let Some(op) = self.get_mut(id) {
+            op.completion = Completion::Ok { at, value };
+        }
+    }
+
+    pub fn fail(&mut self, id: u64) {
+        if let Some(op) = self.get_mut(id) {
+            op.completion = Completion::Info;
+        }
+    }
+
+    pub fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+
+    pub fn clear(&mut self) {
+        self.operations.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordinator::KeyValue;
+
+    fn get(key: &str) -> KVCommand {
+        KVCommand::Get(key.to_string())
+    }
+
+    #[test]
+    fn trims_completed_operations_and_keeps_ids() {
+        let mut history = History::default();
+        for i in 0..=HISTORY_MAX_OPS as u64 {
+            let id = history.invoke(1, &get("x"));
+            assert_eq!(id, i);
+            history.complete(id, None);
+        }
+        let operations = history.operations();
+        assert_eq!(operations.len(), HISTORY_MAX_OPS / 2);
+        assert_eq!(operations[0].id, HISTORY_MAX_OPS as u64 / 2 + 1);
+
+        let id = history.invoke(1, &get("x"));
+        history.complete(id, Some("1".to_string()));
+        let last = history.operations().last().unwrap();
+        assert_eq!(last.id, id);
+        assert!(matches!(&last.completion, Completion::Ok { value: Some(v), .. } if v == "1"));
+    }
+
+    #[test]
+    fn keeps_writes_given_up_on_whose_key_is_used() {
+        let mut history = History::default();
+        for key in ["x", "y"] {
+            let put = KVCommand::Put(KeyValue {
+                key: key.to_string(),
+                value: "1".to_string(),
+            });
+            let id = history.invoke(1, &put);
+            history.fail(id);
+        }
+        for _ in 0..HISTORY_MAX_OPS {
+            let id = history.invoke(1, &get("x"));
+            history.complete(id, None);
+        }
+        let operations = history.operations();
+        assert_eq!(operations.len(), HISTORY_MAX_OPS / 2 + 2);
+        assert_eq!(operations[0].id, 0);
+        assert_eq!(operations[0].completion, Completion::Info);
+        assert_eq!(operations[1].id, HISTORY_MAX_OPS as u64 / 2 + 1);
+
+        let id = history.invoke(1, &get("x"));
+        history.complete(id, Some("1".to_string()));
+        let last = history.operations().last().unwrap();
+        assert!(matches!(&last.completion, Completion::Ok { value: Some(v), .. } if v == "1"));
+    }
+
+    #[test]
+    fn keeps_operations_from_the_first_pending_one() {
+        let mut history = History::default();
+        let id = history.invoke(1, &get("x"));
+        history.complete(id, None);
+        history.invoke(1, &get("x"));
+        for _ in 0..HISTORY_MAX_OPS {
+            let id = history.invoke(1, &get("x"));
+            history.complete(id, None);
+        }
+        assert_eq!(history.operations().len(), HISTORY_MAX_OPS + 1);
+        assert_eq!(history.operations()[0].id, 1);
+    }
+}
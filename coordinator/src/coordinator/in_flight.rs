@@ -0,0 +1,347 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+
+use crate::coordinator::history::History;
+use crate::coordinator::CommandSource;
+use crate::messages::coordinator::KVCommand;
+
+pub(crate) type SharedInFlight = Arc<Mutex<InFlight>>;
+
+/// A command sent to the cluster that has not been acknowledged yet.
+#[derive(Debug, Clone)]
+pub struct InFlightCommand {
+    pub cmd: KVCommand,
+    /// Node the command was explicitly proposed at, `None` if it was routed by the proposer.
+    pub pid: Option<u64>,
+    pub source: CommandSource,
+    /// Node the command was last sent to.
+    pub proposer: u64,
+    pub first_sent: Instant,
+    pub attempts: u32,
+    /// Id of the operation in the history, set when the command is first sent.
+    pub op_id: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum FailureReason {
+    /// Not acknowledged within the proposal timeout.
+    Timeout(Duration),
+    /// The node the command was explicitly proposed at is not connected.
+    NodeUnreachable(u64),
+}
+
+impl fmt::Display for FailureReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FailureReason::Timeout(timeout) => {
+                write!(f, "not acknowledged within {:.1}s", timeout.as_secs_f64())
+            }
+            FailureReason::NodeUnreachable(pid) => write!(f, "node {pid} is not connected"),
+        }
+    }
+}
+
+/// A command the proposer gave up on.
+#[derive(Debug, Clone)]
+pub struct FailedProposal {
+    pub cmd: KVCommand,
+    pub proposer: u64,
+    pub attempts: u32,
+    pub reason: FailureReason,
+}
+
+impl FailedProposal {
+    pub fn new(command: InFlightCommand, reason: FailureReason) -> Self {
+        Self {
+            cmd: command.cmd,
+            proposer: command.proposer,
+            attempts: command.attempts,
+            reason,
+        }
+    }
+}
+
+/// Offset of the request ids from the operation ids. It is derived from the start time of the
+/// coordinator, so the nodes, which skip requests that were already applied, don't take the
+/// writes of a new run for duplicates of an earlier one's.
+#[derive(Debug)]
+struct RequestIdBase(u64);
+
+impl Default for RequestIdBase {
+    fn default() -> Self {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time before 1970")
+            .as_millis() as u64;
+        // Unique as long as a run sends fewer than 2^20 commands per millisecond
+        Self(millis << 20)
+    }
+}
+
+/// Tracks the proposals sent to the cluster until they are acknowledged. Commands are sent with
+/// a request id. Writes are acknowledged when a node reports their id as decided, reads by the
+/// response carrying their id. Routed commands that are unacknowledged when the leader changes are queued for
+/// a retry with the same request id, so the nodes apply a write only once even if both the
+/// original and the retry are decided.
+#[derive(Debug, Default)]
+pub struct InFlight {
+    /// Unacknowledged writes by operation id, oldest first.
+    writes: BTreeMap<u64, InFlightCommand>,
+    /// Unacknowledged reads by operation id, oldest first.
+    reads: BTreeMap<u64, InFlightCommand>,
+    /// Commands waiting to be sent again, oldest first.
+    retries: VecDeque<InFlightCommand>,
+    /// Number of unacknowledged commands sent to each node.
+    outstanding_per_node: HashMap<u64, u64>,
+    /// Writes sent for the first time, i.e. the writes of the clients.
+    proposed_writes: u64,
+    /// Writes sent, including retries.
+    write_attempts: u64,
+    /// When the first write since the last round change was sent.
+    first_write_in_round: Option<Instant>,
+    history: History,
+    request_id_base: RequestIdBase,
+}
+
+impl InFlight {
+    /// Records that `command` was sent. Returns the id of its request, which the nodes report
+    /// back when a write is decided.
+    pub fn sent(&mut self, mut command: InFlightCommand) -> u64 {
+        let first_send = command.op_id.is_none();
+        let op_id = *command
+            .op_id
+            .get_or_insert_with(|| self.history.invoke(command.proposer, &command.cmd));
+        *self
+            .outstanding_per_node
+            .entry(command.proposer)
+            .or_default() += 1;
+        match &command.cmd {
+            KVCommand::Get(_) => {
+                self.reads.insert(op_id, command);
+            }
+            _ => {
+                self.write_attempts += 1;
+                if first_send {
+                    self.proposed_writes += 1;
+                }
+                self.first_write_in_round.get_or_insert_with(Instant::now);
+                self.writes.insert(op_id, command);
+            }
+        }
+        self.request_id_base.0 + op_id
+    }
+
+    fn acked(&mut self, pid: u64) {
+        if let Some(outstanding) = self.outstanding_per_node.get_mut(&pid) {
+            *outstanding = outstanding.saturating_sub(1);
+        }
+    }
+
+    /// Number of write commands sent to the cluster so far, not counting retries.
+    pub fn proposed_writes(&self) -> u64 {
+        self.proposed_writes
+    }
+
+    /// Number of times a write command was sent to the cluster so far, including retries.
+    pub fn write_attempts(&self) -> u64 {
+        self.write_attempts
+    }
+
+    /// When the first write since the last call to `round_changed` was sent.
+    pub fn first_write_in_round(&self) -> Option<Instant> {
+        self.first_write_in_round
+    }
+
+    /// Starts looking for the first write of a new round.
+    pub fn round_changed(&mut self) {
+        self.first_write_in_round = None;
+    }
+
+    pub fn outstanding_writes(&self) -> u64 {
+        let retried_writes = self
+            .retries
+            .iter()
+            .filter(|c| !matches!(c.cmd, KVCommand::Get(_)))
+            .count();
+        (self.writes.len() + retried_writes) as u64
+    }
+
+    /// When the oldest unacknowledged write was first sent.
+    pub fn oldest_pending_write(&self) -> Option<Instant> {
+        self.writes
+            .values()
+            .chain(&self.retries)
+            .filter(|c| !matches!(c.cmd, KVCommand::Get(_)))
+            .map(|c| c.first_sent)
+            .min()
+    }
+
+    pub fn outstanding(&self) -> u64 {
+        (self.writes.len() + self.reads.len() + self.retries.len()) as u64
+    }
+
+    pub fn outstanding_at(&self, pid: u64) -> u64 {
+        self.outstanding_per_node.get(&pid).copied().unwrap_or(0)
+    }
+
+    /// Acknowledges the writes of the requests `request_ids`, which a node reported as decided.
+    /// Ids of writes that were already acknowledged, e.g. when the original and a retry of a
+    /// command are both decided, are ignored. Returns the latencies of the acknowledged writes.
+    pub fn handle_decided(&mut self, request_ids: &[u64]) -> Vec<Duration> {
+        let now = Instant::now();
+        let mut latencies = vec![];
+        for request_id in request_ids {
+            // Requests of an earlier run of the coordinator
+            let Some(op_id) = request_id.checked_sub(self.request_id_base.0) else {
+                continue;
+            };
+            let command = match self.writes.remove(&op_id) {
+                Some(command) => {
+                    self.acked(command.proposer);
+                    command
+                }
+                // The command was decided before its retry was sent
+                None => match self.retries.iter().position(|c| c.op_id == Some(op_id)) {
+                    Some(idx) => self.retries.remove(idx).unwrap(),
+                    None => continue,
+                },
+            };
+            self.history.complete(op_id, None);
+            latencies.push(now - command.first_sent);
+        }
+        latencies
+    }
+
+    /// Acknowledges the read of the request `request_id`, which returned `value`. Returns its
+    /// latency and who queued it.
+    pub fn handle_read(
+        &mut self,
+        request_id: u64,
+        value: Option<String>,
+    ) -> Option<(Duration, CommandSource)> {
+        let op_id = request_id.checked_sub(self.request_id_base.0)?;
+        let command = match self.reads.remove(&op_id) {
+            Some(command) => {
+                self.acked(command.proposer);
+                command
+            }
+            // The read was answered before its retry was sent
+            None => {
+                let idx = self.retries.iter().position(|c| c.op_id == Some(op_id))?;
+                self.retries.remove(idx).unwrap()
+            }
+        };
+        self.history.complete(op_id, value);
+        Some((command.first_sent.elapsed(), command.source))
+    }
+
+    /// Removes the unacknowledged commands matching `filter` from the in-flight commands.
+    fn take_unacked<F>(&mut self, filter: F) -> Vec<InFlightCommand>
+    where
+        F: Fn(&InFlightCommand) -> bool,
+    {
+        let mut taken = vec![];
+        for commands in [&mut self.writes, &mut self.reads] {
+            let (matching, rest): (BTreeMap<_, _>, BTreeMap<_, _>) = std::mem::take(commands)
+                .into_iter()
+                .partition(|(_, command)| filter(command));
+            *commands = rest;
+            taken.extend(matching.into_values());
+        }
+        for command in &taken {
+            self.acked(command.proposer);
+        }
+        taken
+    }
+
+    /// Queues all unacknowledged routed commands to be sent again. Returns how many there were.
+    pub fn retry_routed(&mut self) -> usize {
+        let mut retries = self.take_unacked(|c| c.pid.is_none());
+        retries.sort_by_key(|c| c.first_sent);
+        let num_retries = retries.len();
+        self.retries.extend(retries);
+        num_retries
+    }
+
+    /// Queues a command that could not be sent to be sent again.
+    pub fn retry(&mut self, command: InFlightCommand) {
+        self.retries.push_back(command);
+    }
+
+    /// Takes the next command to retry.
+    pub fn next_retry(&mut self) -> Option<InFlightCommand> {
+        self.retries.pop_front()
+    }
+
+    pub fn has_retries(&self) -> bool {
+        !self.retries.is_empty()
+    }
+
+    /// Removes and returns the commands that were first sent more than `timeout` ago.
+    pub fn take_timed_out(&mut self, timeout: Duration) -> Vec<InFlightCommand> {
+        let now = Instant::now();
+        let timed_out = |c: &InFlightCommand| now - c.first_sent > timeout;
+        let mut expired = self.take_unacked(timed_out);
+        let (old, pending): (VecDeque<_>, VecDeque<_>) =
+            self.retries.drain(..).partition(timed_out);
+        self.retries = pending;
+        expired.extend(old);
+        for id in expired.iter().filter_map(|c| c.op_id) {
+            self.history.fail(id);
+        }
+        expired
+    }
+
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordinator::history::Completion;
+
+    fn read(key: &str, proposer: u64) -> InFlightCommand {
+        InFlightCommand {
+            cmd: KVCommand::Get(key.to_string()),
+            pid: None,
+            source: CommandSource::User,
+            proposer,
+            first_sent: Instant::now(),
+            attempts: 1,
+            op_id: None,
+        }
+    }
+
+    #[test]
+    fn completes_reads_by_request_id() {
+        let mut in_flight = InFlight::default();
+        let first = in_flight.sent(read("x", 1));
+        let second = in_flight.sent(read("x", 2));
+        assert!(in_flight
+            .handle_read(second, Some("2".to_string()))
+            .is_some());
+        assert!(in_flight.handle_read(first, None).is_some());
+        assert!(in_flight.handle_read(first, None).is_none());
+        assert_eq!(in_flight.outstanding(), 0);
+
+        let values: Vec<_> = in_flight
+            .history()
+            .operations()
+            .iter()
+            .map(|op| match &op.completion {
+                Completion::Ok { value, .. } => value.clone(),
+                completion => panic!("read not completed: {completion:?}"),
+            })
+            .collect();
+        assert_eq!(values, vec![None, Some("2".to_string())]);
+    }
+}
@@ -0,0 +1,275 @@
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::time::Instant;
+
+/// Type of a message between OmniPaxos nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum MessageKind {
+    HeartbeatRequest,
+    HeartbeatReply,
+    PrepareReq,
+    Prepare,
+    Promise,
+    AcceptSync,
+    AcceptDecide,
+    Accepted,
+    NotAccepted,
+    Decide,
+    ProposalForward,
+    Compaction,
+    StopSign,
+    /// Not an OmniPaxos message, or one this version of the coordinator doesn't know.
+    Unknown,
+}
+
+impl MessageKind {
+    /// Short name used as column header.
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            MessageKind::HeartbeatRequest => "HB req",
+            MessageKind::HeartbeatReply => "HB rep",
+            MessageKind::PrepareReq => "PrepReq",
+            MessageKind::Prepare => "Prep",
+            MessageKind::Promise => "Prom",
+            MessageKind::AcceptSync => "AccSync",
+            MessageKind::AcceptDecide => "AccDec",
+            MessageKind::Accepted => "Acc'd",
+            MessageKind::NotAccepted => "NotAcc",
+            MessageKind::Decide => "Dec",
+            MessageKind::ProposalForward => "Fwd",
+            MessageKind::Compaction => "Compact",
+            MessageKind::StopSign => "SS",
+            MessageKind::Unknown => "?",
+        }
+    }
+}
+
+impl fmt::Display for MessageKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            MessageKind::HeartbeatRequest => "BLE heartbeat request",
+            MessageKind::HeartbeatReply => "BLE heartbeat reply",
+            MessageKind::PrepareReq => "PrepareReq",
+            MessageKind::Prepare => "Prepare",
+            MessageKind::Promise => "Promise",
+            MessageKind::AcceptSync => "AcceptSync",
+            MessageKind::AcceptDecide => "AcceptDecide",
+            MessageKind::Accepted => "Accepted",
+            MessageKind::NotAccepted => "NotAccepted",
+            MessageKind::Decide => "Decide",
+            MessageKind::ProposalForward => "ProposalForward",
+            MessageKind::Compaction => "Compaction",
+            MessageKind::StopSign => "StopSign",
+            MessageKind::Unknown => "unknown",
+        };
+        write!(f, "{name}")
+    }
+}
+
+// Shapes of the messages sent between nodes, as serialized by the KV demo. Only the variant
+// names are decoded, the contents are skipped.
+
+#[derive(Deserialize)]
+enum WireMessage {
+    OmniPaxosMsg(OmniPaxosMessage),
+}
+
+#[derive(Deserialize)]
+#[allow(clippy::upper_case_acronyms)]
+enum OmniPaxosMessage {
+    SequencePaxos(Envelope<PaxosMsg>),
+    BLE(Envelope<HeartbeatMsg>),
+}
+
+#[derive(Deserialize)]
+struct Envelope<M> {
+    msg: M,
+}
+
+#[derive(Deserialize)]
+enum PaxosMsg {
+    PrepareReq(IgnoredAny),
+    Prepare(IgnoredAny),
+    Promise(IgnoredAny),
+    AcceptSync(IgnoredAny),
+    AcceptDecide(IgnoredAny),
+    Accepted(IgnoredAny),
+    NotAccepted(IgnoredAny),
+    Decide(IgnoredAny),
+    ProposalForward(IgnoredAny),
+    Compaction(IgnoredAny),
+    AcceptStopSign(IgnoredAny),
+    ForwardStopSign(IgnoredAny),
+}
+
+#[derive(Deserialize)]
+enum HeartbeatMsg {
+    Request(IgnoredAny),
+    Reply(IgnoredAny),
+}
+
+/// Decodes the type of a message sent between two nodes.
+pub fn classify(data: &[u8]) -> MessageKind {
+    let Ok(WireMessage::OmniPaxosMsg(msg)) = serde_json::from_slice(data) else {
+        return MessageKind::Unknown;
+    };
+    match msg {
+        OmniPaxosMessage::BLE(Envelope { msg }) => match msg {
+            HeartbeatMsg::Request(_) => MessageKind::HeartbeatRequest,
+            HeartbeatMsg::Reply(_) => MessageKind::HeartbeatReply,
+        },
+        OmniPaxosMessage::SequencePaxos(Envelope { msg }) => match msg {
+            PaxosMsg::PrepareReq(_) => MessageKind::PrepareReq,
+            PaxosMsg::Prepare(_) => MessageKind::Prepare,
+            PaxosMsg::Promise(_) => MessageKind::Promise,
+            PaxosMsg::AcceptSync(_) => MessageKind::AcceptSync,
+            PaxosMsg::AcceptDecide(_) => MessageKind::AcceptDecide,
+            PaxosMsg::Accepted(_) => MessageKind::Accepted,
+            PaxosMsg::NotAccepted(_) => MessageKind::NotAccepted,
+            PaxosMsg::Decide(_) => MessageKind::Decide,
+            PaxosMsg::ProposalForward(_) => MessageKind::ProposalForward,
+            PaxosMsg::Compaction(_) => MessageKind::Compaction,
+            PaxosMsg::AcceptStopSign(_) | PaxosMsg::ForwardStopSign(_) => MessageKind::StopSign,
+        },
+    }
+}
+
+/// Protocol phase the cluster appears to be in, judging by the messages it sends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProtocolPhase {
+    /// Only heartbeats, or nothing at all.
+    Idle,
+    /// A leader is gathering promises.
+    Prepare,
+    /// A new leader is synchronizing the logs of its followers.
+    Sync,
+    /// The leader is replicating and deciding new entries.
+    Replication,
+}
+
+impl fmt::Display for ProtocolPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ProtocolPhase::Idle => "idle",
+            ProtocolPhase::Prepare => "prepare (leader takeover)",
+            ProtocolPhase::Sync => "log synchronization",
+            ProtocolPhase::Replication => "replication",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Messages sent over a directed link.
+#[derive(Debug, Clone, Default)]
+pub struct LinkFlow {
+    pub from: u64,
+    pub to: u64,
+    /// Messages per second of each type over the last report interval.
+    pub rates: BTreeMap<MessageKind, f64>,
+    /// Dropped messages per second over the last report interval.
+    pub dropped_rate: f64,
+    /// Messages of each type since the inspection started.
+    pub totals: BTreeMap<MessageKind, u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MessageFlowReport {
+    pub links: Vec<LinkFlow>,
+    pub phase: ProtocolPhase,
+}
+
+#[derive(Debug, Default)]
+struct LinkCounters {
+    interval: BTreeMap<MessageKind, u64>,
+    interval_dropped: u64,
+    totals: BTreeMap<MessageKind, u64>,
+}
+
+/// Counts the messages between nodes by type, per directed link.
+#[derive(Debug)]
+pub struct MessageInspector {
+    pub enabled: bool,
+    links: HashMap<(u64, u64), LinkCounters>,
+    interval_start: Instant,
+}
+
+impl MessageInspector {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            links: HashMap::new(),
+            interval_start: Instant::now(),
+        }
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.links.clear();
+        self.interval_start = Instant::now();
+    }
+
+    pub fn count(&mut self, from: u64, to: u64, kind: MessageKind, dropped: bool) {
+        let link = self.links.entry((from, to)).or_default();
+        *link.totals.entry(kind).or_default() += 1;
+        match dropped {
+            true => link.interval_dropped += 1,
+            false => *link.interval.entry(kind).or_default() += 1,
+        }
+    }
+
+    /// Reports the message rates since the last report.
+    pub fn report(&mut self) -> MessageFlowReport {
+        let elapsed = self
+            .interval_start
+            .elapsed()
+            .as_secs_f64()
+            .max(f64::EPSILON);
+        self.interval_start = Instant::now();
+        let mut interval_totals: BTreeMap<MessageKind, u64> = BTreeMap::new();
+        let mut links: Vec<LinkFlow> = self
+            .links
+            .iter_mut()
+            .map(|(&(from, to), counters)| {
+                for (kind, count) in &counters.interval {
+                    *interval_totals.entry(*kind).or_default() += count;
+                }
+                let interval = std::mem::take(&mut counters.interval);
+                let dropped = std::mem::take(&mut counters.interval_dropped);
+                LinkFlow {
+                    from,
+                    to,
+                    rates: interval
+                        .into_iter()
+                        .map(|(kind, count)| (kind, count as f64 / elapsed))
+                        .collect(),
+                    dropped_rate: dropped as f64 / elapsed,
+                    totals: counters.totals.clone(),
+                }
+            })
+            .collect();
+        links.sort_by_key(|link| (link.from, link.to));
+        MessageFlowReport {
+            links,
+            phase: phase(&interval_totals),
+        }
+    }
+}
+
+fn phase(counts: &BTreeMap<MessageKind, u64>) -> ProtocolPhase {
+    let has = |kinds: &[MessageKind]| kinds.iter().any(|k| counts.contains_key(k));
+    if has(&[MessageKind::Prepare, MessageKind::Promise]) {
+        ProtocolPhase::Prepare
+    } else if has(&[MessageKind::AcceptSync]) {
+        ProtocolPhase::Sync
+    } else if has(&[
+        MessageKind::AcceptDecide,
+        MessageKind::Accepted,
+        MessageKind::Decide,
+    ]) {
+        ProtocolPhase::Replication
+    } else {
+        ProtocolPhase::Idle
+    }
+}
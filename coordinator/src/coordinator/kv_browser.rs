@@ -0,0 +1,113 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::coordinator::KeyValue;
+
+/// Value of a key at each node that was scanned, in the order of `KVComparison::nodes`. `None`
+/// if the node doesn't have the key.
+#[derive(Debug, Clone)]
+pub struct KVRow {
+    pub key: String,
+    pub values: Vec<Option<String>>,
+    /// Whether the nodes disagree on the value.
+    pub divergent: bool,
+}
+
+/// Keys and values read from the database of each node, side by side.
+#[derive(Debug, Clone, Default)]
+pub struct KVComparison {
+    pub nodes: Vec<u64>,
+    /// Nodes whose last scan was before they were paused or their client link was cut.
+    pub stale: Vec<u64>,
+    /// Divergent keys first, then in key order.
+    pub rows: Vec<KVRow>,
+    /// Whether some node has more keys than were scanned.
+    pub truncated: bool,
+}
+
+struct NodeScan {
+    entries: Vec<KeyValue>,
+    stale: bool,
+}
+
+/// Latest scan of the database of each node.
+pub struct KVBrowser {
+    scans: HashMap<u64, NodeScan>,
+    /// Keys asked for in each scan.
+    limit: usize,
+}
+
+impl KVBrowser {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            scans: HashMap::new(),
+            limit,
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn record_scan(&mut self, pid: u64, entries: Vec<KeyValue>) {
+        self.scans.insert(
+            pid,
+            NodeScan {
+                entries,
+                stale: false,
+            },
+        );
+    }
+
+    /// Marks the scan of a node that can't be scanned anymore as stale.
+    pub fn mark_stale(&mut self, pid: u64) {
+        if let Some(scan) = self.scans.get_mut(&pid) {
+            scan.stale = true;
+        }
+    }
+
+    pub fn forget(&mut self, pid: u64) {
+        self.scans.remove(&pid);
+    }
+
+    pub fn compare(&self) -> KVComparison {
+        let mut nodes: Vec<u64> = self.scans.keys().copied().collect();
+        nodes.sort();
+        // Keys after the last one of a truncated scan may be missing from it, so they can't be
+        // compared
+        let truncated_at = self
+            .scans
+            .values()
+            .filter(|scan| scan.entries.len() >= self.limit)
+            .filter_map(|scan| scan.entries.last().map(|kv| kv.key.as_str()))
+            .min();
+        let mut keys: BTreeMap<&str, Vec<Option<String>>> = BTreeMap::new();
+        for (i, pid) in nodes.iter().enumerate() {
+            let entries = self.scans[pid].entries.iter();
+            for kv in
+                entries.take_while(|kv| !matches!(truncated_at, Some(k) if kv.key.as_str() > k))
+            {
+                keys.entry(&kv.key)
+                    .or_insert_with(|| vec![None; nodes.len()])[i] = Some(kv.value.clone());
+            }
+        }
+        let mut rows: Vec<KVRow> = keys
+            .into_iter()
+            .map(|(key, values)| KVRow {
+                key: key.to_string(),
+                divergent: values.iter().any(|value| *value != values[0]),
+                values,
+            })
+            .collect();
+        rows.sort_by_key(|row| !row.divergent);
+        KVComparison {
+            stale: nodes
+                .iter()
+                .copied()
+                .filter(|pid| self.scans[pid].stale)
+                .collect(),
+            truncated: truncated_at.is_some(),
+            nodes,
+            rows,
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use hdrhistogram::Histogram;
+use std::time::{Duration, Instant};
+
+use crate::utils::LATENCY_MAX_TRACKABLE;
+
+/// Latency percentiles of a set of acknowledged proposals.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LatencySummary {
+    pub count: u64,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl LatencySummary {
+    fn from_histogram(histogram: &Histogram<u64>) -> Self {
+        let at = |quantile| Duration::from_micros(histogram.value_at_quantile(quantile));
+        Self {
+            count: histogram.len(),
+            p50: at(0.5),
+            p95: at(0.95),
+            p99: at(0.99),
+            max: Duration::from_micros(histogram.max()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct LatencyReport {
+    /// Seconds since the coordinator started.
+    pub elapsed: f64,
+    /// All latencies recorded so far.
+    pub overall: LatencySummary,
+    /// Latencies recorded since the previous report, if any.
+    pub interval: Option<LatencySummary>,
+}
+
+/// Records the time from sending a proposal until it is acknowledged.
+pub struct LatencyRecorder {
+    started: Instant,
+    overall: Histogram<u64>,
+    interval: Histogram<u64>,
+}
+
+impl LatencyRecorder {
+    pub fn new() -> Self {
+        let histogram = || {
+            Histogram::new_with_bounds(1, LATENCY_MAX_TRACKABLE.as_micros() as u64, 3)
+                .expect("invalid histogram bounds")
+        };
+        Self {
+            started: Instant::now(),
+            overall: histogram(),
+            interval: histogram(),
+        }
+    }
+
+    pub fn record(&mut self, latency: Duration) {
+        let micros = latency.as_micros() as u64;
+        self.overall.saturating_record(micros);
+        self.interval.saturating_record(micros);
+    }
+
+    /// Returns the current latencies and starts a new interval.
+    pub fn report(&mut self) -> LatencyReport {
+        let interval =
+            (!self.interval.is_empty()).then(|| LatencySummary::from_histogram(&self.interval));
+        self.interval.reset();
+        LatencyReport {
+            elapsed: self.started.elapsed().as_secs_f64(),
+            overall: LatencySummary::from_histogram(&self.overall),
+            interval,
+        }
+    }
+}
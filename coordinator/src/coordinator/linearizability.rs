@@ -0,0 +1,405 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use crate::coordinator::history::{Completion, Operation};
+use crate::messages::coordinator::KVCommand;
+use crate::utils::{CHECK_MAX_SEARCH_STEPS, CHECK_MAX_SHRINK_OPS};
+
+#[derive(Debug, Clone)]
+pub enum CheckOutcome {
+    Linearizable,
+    /// The operations on `key` can't be linearized. `anomaly` is a minimal sub-history of them
+    /// that can't be linearized either.
+    NotLinearizable {
+        key: String,
+        anomaly: Vec<Operation>,
+    },
+    /// The search for a linearization of the operations on `key` exceeded its budget.
+    Unknown {
+        key: String,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckReport {
+    pub operations: usize,
+    pub keys: usize,
+    pub outcome: CheckOutcome,
+    pub elapsed: Duration,
+}
+
+/// Checks whether `history` is linearizable with respect to a key-value store. Since
+/// linearizability is compositional, every key is checked on its own.
+pub fn check(history: &[Operation]) -> CheckReport {
+    let start = Instant::now();
+    let mut per_key: BTreeMap<&str, Vec<Operation>> = BTreeMap::new();
+    for op in history {
+        // Reads without a response don't constrain anything
+        if matches!(op.cmd, KVCommand::Get(_)) && !matches!(op.completion, Completion::Ok { .. }) {
+            continue;
+        }
+        per_key.entry(op.key()).or_default().push(op.clone());
+    }
+    let mut outcome = CheckOutcome::Linearizable;
+    for (key, ops) in &per_key {
+        match search(ops) {
+            SearchResult::Linearizable => continue,
+            SearchResult::NotLinearizable { stuck_at } => {
+                outcome = CheckOutcome::NotLinearizable {
+                    key: key.to_string(),
+                    anomaly: shrink(ops, stuck_at),
+                };
+            }
+            SearchResult::Unknown => {
+                outcome = CheckOutcome::Unknown {
+                    key: key.to_string(),
+                }
+            }
+        }
+        break;
+    }
+    CheckReport {
+        operations: history.len(),
+        keys: per_key.len(),
+        outcome,
+        elapsed: start.elapsed(),
+    }
+}
+
+/// Reduces a non-linearizable history of one key to a sub-history that is still not
+/// linearizable, but becomes linearizable when any single operation is removed.
+fn shrink(ops: &[Operation], stuck_at: Duration) -> Vec<Operation> {
+    // Only consider the operations around the point where the search got stuck
+    let mut window: Vec<Operation> = ops
+        .iter()
+        .filter(|op| op.invoke <= stuck_at)
+        .cloned()
+        .collect();
+    if window.len() > CHECK_MAX_SHRINK_OPS {
+        window.drain(..window.len() - CHECK_MAX_SHRINK_OPS);
+    }
+    if !matches!(search(&window), SearchResult::NotLinearizable { .. }) {
+        return window;
+    }
+    let mut i = window.len();
+    while i > 0 {
+        i -= 1;
+        let mut candidate = window.clone();
+        candidate.remove(i);
+        if matches!(search(&candidate), SearchResult::NotLinearizable { .. }) {
+            window = candidate;
+        }
+    }
+    window
+}
+
+enum SearchResult {
+    Linearizable,
+    /// No linearization gets past the response of an operation at `stuck_at`.
+    NotLinearizable {
+        stuck_at: Duration,
+    },
+    Unknown,
+}
+
+/// Value of a key, with values interned to make states cheap to compare.
+type Value = Option<u32>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum State {
+    /// The key may hold anything written before the history started.
+    Unknown,
+    Known(Value),
+}
+
+enum Input {
+    Write(Value),
+    Read(Value),
+}
+
+impl Input {
+    fn step(&self, state: State) -> Option<State> {
+        match (self, state) {
+            (Input::Write(value), _) => Some(State::Known(*value)),
+            (Input::Read(value), State::Unknown) => Some(State::Known(*value)),
+            (Input::Read(value), State::Known(current)) => (*value == current).then_some(state),
+        }
+    }
+}
+
+/// Doubly linked list of call and return events ordered by time. Calls know the index of
+/// their return.
+struct Events {
+    next: Vec<Option<usize>>,
+    prev: Vec<usize>,
+    /// For call events, the index of the matching return event.
+    matching: Vec<Option<usize>>,
+    op: Vec<usize>,
+    time: Vec<Duration>,
+}
+
+impl Events {
+    const HEAD: usize = 0;
+
+    fn new(calls: &[(Duration, Duration)]) -> Self {
+        let mut events: Vec<(Duration, bool, usize)> = calls
+            .iter()
+            .enumerate()
+            .flat_map(|(op, (call, ret))| [(*call, true, op), (*ret, false, op)])
+            .collect();
+        // Calls go before returns at the same time, which makes the operations overlap
+        events.sort_by_key(|(time, is_call, _)| (*time, !*is_call));
+        let len = events.len() + 1;
+        let mut list = Self {
+            next: (1..=len).map(|i| (i < len).then_some(i)).collect(),
+            prev: (0..len).map(|i| i.saturating_sub(1)).collect(),
+            matching: vec![None; len],
+            op: vec![0; len],
+            time: vec![Duration::ZERO; len],
+        };
+        let mut call_of_op = HashMap::new();
+        for (i, (time, is_call, op)) in events.into_iter().enumerate() {
+            let idx = i + 1;
+            list.op[idx] = op;
+            list.time[idx] = time;
+            if is_call {
+                call_of_op.insert(op, idx);
+            } else {
+                list.matching[call_of_op[&op]] = Some(idx);
+            }
+        }
+        list
+    }
+
+    fn unlink(&mut self, idx: usize) {
+        let prev = self.prev[idx];
+        self.next[prev] = self.next[idx];
+        if let Some(next) = self.next[idx] {
+            self.prev[next] = prev;
+        }
+    }
+
+    fn relink(&mut self, idx: usize) {
+        let prev = self.prev[idx];
+        self.next[prev] = Some(idx);
+        if let Some(next) = self.next[idx] {
+            self.prev[next] = idx;
+        }
+    }
+
+    /// Removes a call and its return from the list.
+    fn lift(&mut self, call: usize) {
+        self.unlink(call);
+        self.unlink(self.matching[call].unwrap());
+    }
+
+    /// Undoes `lift`.
+    fn unlift(&mut self, call: usize) {
+        self.relink(self.matching[call].unwrap());
+        self.relink(call);
+    }
+}
+
+/// Searches for a linearization of the operations on a single key, following the algorithm of
+/// Wing & Gong with the state cache of Lowe, as used by Porcupine.
+fn search(ops: &[Operation]) -> SearchResult {
+    fn intern<'a>(values: &mut HashMap<&'a str, u32>, value: Option<&'a String>) -> Value {
+        value.map(|v| {
+            let next_id = values.len() as u32;
+            *values.entry(v.as_str()).or_insert(next_id)
+        })
+    }
+    let mut values = HashMap::new();
+    let inputs: Vec<Input> = ops
+        .iter()
+        .map(|op| match (&op.cmd, &op.completion) {
+            (KVCommand::Put(kv), _) => Input::Write(intern(&mut values, Some(&kv.value))),
+            (KVCommand::Delete(_), _) => Input::Write(None),
+            (KVCommand::Get(_), Completion::Ok { value, .. }) => {
+                Input::Read(intern(&mut values, value.as_ref()))
+            }
+            (KVCommand::Get(_), _) => unreachable!("reads without a response are not checked"),
+        })
+        .collect();
+    // Operations without a response may take effect at any point after their invocation
+    let bounds: Vec<(Duration, Duration)> = ops
+        .iter()
+        .map(|op| match op.completion {
+            Completion::Ok { at, .. } => (op.invoke, at),
+            _ => (op.invoke, Duration::MAX),
+        })
+        .collect();
+
+    let mut events = Events::new(&bounds);
+    let mut linearized = vec![0u64; ops.len() / 64 + 1];
+    let mut cache: HashSet<(Vec<u64>, State)> = HashSet::new();
+    let mut calls: Vec<(usize, State)> = vec![];
+    let mut state = State::Unknown;
+    let mut entry = events.next[Events::HEAD];
+    let mut stuck_at = Duration::ZERO;
+    let mut steps = 0;
+    while let Some(idx) = entry {
+        steps += 1;
+        if steps > CHECK_MAX_SEARCH_STEPS {
+            return SearchResult::Unknown;
+        }
+        let op = events.op[idx];
+        if events.matching[idx].is_some() {
+            // Try to linearize the operation at this point
+            let mut next_linearized = linearized.clone();
+            next_linearized[op / 64] |= 1 << (op % 64);
+            match inputs[op].step(state) {
+                Some(next_state) if cache.insert((next_linearized.clone(), next_state)) => {
+                    calls.push((idx, state));
+                    state = next_state;
+                    linearized = next_linearized;
+                    events.lift(idx);
+                    entry = events.next[Events::HEAD];
+                }
+                _ => entry = events.next[idx],
+            }
+        } else {
+            // The operation returned before being linearized, backtrack
+            stuck_at = stuck_at.max(events.time[idx]);
+            let Some((call, previous_state)) = calls.pop() else {
+                return SearchResult::NotLinearizable { stuck_at };
+            };
+            let call_op = events.op[call];
+            linearized[call_op / 64] &= !(1 << (call_op % 64));
+            state = previous_state;
+            events.unlift(call);
+            entry = events.next[call];
+        }
+    }
+    SearchResult::Linearizable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordinator::KeyValue;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    fn put(id: u64, key: &str, value: &str, invoke: u64, ok_at: Option<u64>) -> Operation {
+        Operation {
+            id,
+            process: 1,
+            cmd: KVCommand::Put(KeyValue {
+                key: key.to_string(),
+                value: value.to_string(),
+            }),
+            invoke: ms(invoke),
+            completion: match ok_at {
+                Some(at) => Completion::Ok {
+                    at: ms(at),
+                    value: None,
+                },
+                None => Completion::Info,
+            },
+        }
+    }
+
+    fn get(id: u64, key: &str, value: Option<&str>, invoke: u64, ok_at: u64) -> Operation {
+        Operation {
+            id,
+            process: 1,
+            cmd: KVCommand::Get(key.to_string()),
+            invoke: ms(invoke),
+            completion: Completion::Ok {
+                at: ms(ok_at),
+                value: value.map(str::to_string),
+            },
+        }
+    }
+
+    fn anomaly_ids(report: &CheckReport) -> Vec<u64> {
+        match &report.outcome {
+            CheckOutcome::NotLinearizable { anomaly, .. } => {
+                anomaly.iter().map(|op| op.id).collect()
+            }
+            outcome => panic!("expected an anomaly, got {outcome:?}"),
+        }
+    }
+
+    #[test]
+    fn sequential_history_is_linearizable() {
+        let history = vec![
+            get(0, "x", None, 0, 5),
+            put(1, "x", "1", 10, Some(20)),
+            get(2, "x", Some("1"), 30, 40),
+            put(3, "y", "2", 30, Some(40)),
+            get(4, "y", Some("2"), 50, 60),
+        ];
+        let report = check(&history);
+        assert!(matches!(report.outcome, CheckOutcome::Linearizable));
+        assert_eq!(report.keys, 2);
+    }
+
+    #[test]
+    fn stale_read_is_not_linearizable() {
+        let history = vec![
+            put(0, "x", "1", 0, Some(10)),
+            put(1, "x", "2", 20, Some(30)),
+            get(2, "x", Some("1"), 40, 50),
+        ];
+        let report = check(&history);
+        assert_eq!(anomaly_ids(&report), vec![1, 2]);
+    }
+
+    #[test]
+    fn concurrent_writes_may_take_effect_in_any_order() {
+        // Both writes overlap both reads, so they can be ordered put(2), get, put(1), get
+        let history = vec![
+            put(0, "x", "1", 0, Some(100)),
+            put(1, "x", "2", 0, Some(100)),
+            get(2, "x", Some("2"), 10, 20),
+            get(3, "x", Some("1"), 30, 40),
+        ];
+        assert!(matches!(
+            check(&history).outcome,
+            CheckOutcome::Linearizable
+        ));
+
+        // Once both writes completed, reads can't switch between their values
+        let history = vec![
+            put(0, "x", "1", 0, Some(10)),
+            put(1, "x", "2", 0, Some(10)),
+            get(2, "x", Some("2"), 20, 30),
+            get(3, "x", Some("1"), 40, 50),
+        ];
+        assert_eq!(anomaly_ids(&check(&history)), vec![2, 3]);
+    }
+
+    #[test]
+    fn failed_writes_may_take_effect_late_but_not_early() {
+        let history = vec![
+            put(0, "x", "1", 0, Some(10)),
+            put(1, "x", "2", 20, None),
+            get(2, "x", Some("1"), 30, 40),
+            get(3, "x", Some("2"), 1000, 1010),
+        ];
+        assert!(matches!(
+            check(&history).outcome,
+            CheckOutcome::Linearizable
+        ));
+
+        let history = vec![
+            put(0, "x", "1", 0, Some(10)),
+            get(1, "x", Some("2"), 12, 15),
+            put(2, "x", "2", 20, None),
+        ];
+        assert_eq!(anomaly_ids(&check(&history)), vec![0, 1]);
+    }
+
+    #[test]
+    fn reads_without_a_response_are_ignored() {
+        let mut failed_read = get(1, "x", None, 20, 30);
+        failed_read.completion = Completion::Info;
+        let history = vec![put(0, "x", "1", 0, Some(10)), failed_read];
+        let report = check(&history);
+        assert!(matches!(report.outcome, CheckOutcome::Linearizable));
+    }
+}
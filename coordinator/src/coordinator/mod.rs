@@ -1,4 +1,4 @@
-use crate::messages::coordinator::{APIResponse, CDMessage, KVCommand, Message, Round};
+use crate::messages::coordinator::{APIResponse, CDMessage, KVCommand, Message, NodeStatus, Round};
 use crate::messages::ui::UIMessage;
 use crate::messages::IOMessage;
 use rand::random;
@@ -11,16 +11,67 @@ use std::{collections::HashMap, sync::Arc};
 use tokio::join;
 use tokio::net::tcp::OwnedWriteHalf;
 use tokio::sync::mpsc::{Receiver, Sender};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
 use tokio::{
     io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
     net::TcpListener,
-    sync::{broadcast, mpsc, Mutex},
+    sync::{mpsc, watch, Mutex},
 };
+use tracing::{debug, info, info_span, instrument, trace, warn, Instrument};
 
-use self::proposal_streamer::ProposalStreamer;
+use self::bandwidth::{LinkQueueStatus, LinkQueues, TokenBucket};
+use self::chaos::{ChaosAction, ChaosConfig, ChaosRunner};
+use self::downtime::DowntimeMonitor;
+use self::filter::{queue_delayed, FilterAction, FilterRule, MessageFilter};
+use self::freeze::{is_paused, Freeze, Freezes, HeldRequests, RequestGate};
+use self::in_flight::{FailedProposal, FailureReason, InFlight, SharedInFlight};
+use self::inspector::{classify, MessageInspector};
+use self::kv_browser::KVBrowser;
+use self::latency::LatencyRecorder;
+use self::partition::{complete_groups, cut_links, format_groups};
+use self::proposal_streamer::{ProposalStreamer, ProposerConfig};
+use self::quorum::QuorumAnalysis;
+use self::replay::{ReplayControl, ReplayPlayer};
+use self::timeline::ElectionTimeline;
+use self::traffic::{TrafficRecorder, Verdict};
+use self::workload::{WorkloadRunner, WorkloadSpec};
+use crate::utils::{
+    format_bandwidth, get_arg_value, CHANNEL_BUFFER_SIZE, DEFAULT_DOWNTIME_REPORT_FILE,
+    DOWNTIME_CHECK_RATE, KV_SCAN_MAX_KEYS, KV_SCAN_RATE, LATENCY_REPORT_RATE,
+    LINK_QUEUE_REPORT_RATE, MESSAGE_FLOW_REPORT_RATE, PROPOSAL_TIMEOUT_CHECK_RATE, SCENARIO_NODES,
+};
+pub mod bandwidth;
+pub mod chaos;
+pub mod downtime;
+pub mod filter;
+pub mod freeze;
+pub mod history;
+pub mod in_flight;
+pub mod inspector;
+pub mod kv_browser;
+pub mod latency;
+pub mod linearizability;
+pub mod partition;
 pub mod proposal_streamer;
+pub mod quorum;
+pub mod replay;
+pub mod timeline;
+pub mod traffic;
+pub mod workload;
 const BATCH_KEY: &str = "BATCH_KEY";
 
+/// Commands waiting to be proposed, with an optional node to propose them at and who queued
+/// them.
+pub(crate) type CommandQueue = Arc<Mutex<VecDeque<(KVCommand, Option<u64>, CommandSource)>>>;
+
+/// Who queued a command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandSource {
+    User,
+    Workload,
+}
+
 fn connection_to_port(from: &u64, to: &u64) -> u64 {
     8000 + (from * 10) + to
 }
@@ -36,7 +87,7 @@ fn port_to_connection(port: &u64) -> (u64, u64) {
 
 lazy_static! {
     // Pids of nodes in the cluster
-    static ref NODES: Vec<u64> = if let Ok(var) = env::var("NODES") {
+    pub(crate) static ref NODES: Vec<u64> = if let Ok(var) = env::var("NODES") {
         serde_json::from_str(&var).expect("wrong config format")
     } else {
         panic!("missing config");
@@ -56,6 +107,25 @@ lazy_static! {
         port_mappings
     };
 
+    /// Read and write quorum sizes, if the nodes use flexible quorums.
+    static ref FLEX_QUORUM: Option<(usize, usize)> = env::var("FLEX_QUORUM").ok().map(|var| {
+        let sizes: Vec<usize> = serde_json::from_str(&var).expect("wrong config format");
+        match sizes[..] {
+            [read, write] => (read, write),
+            _ => panic!("wrong config format"),
+        }
+    });
+
+    /// File the downtime windows are reported to.
+    static ref DOWNTIME_REPORT_FILE: String = get_arg_value("--downtime-report")
+        .unwrap_or(DEFAULT_DOWNTIME_REPORT_FILE.to_string());
+
+    /// File the proxied traffic is recorded to, if any.
+    static ref TRAFFIC_RECORD_FILE: Option<String> = get_arg_value("--record");
+
+    /// Whether the proxy decodes the messages between nodes from the start.
+    static ref INSPECT_MESSAGES: bool = std::env::args().any(|arg| arg == "--inspect");
+
     /// Ports on which the nodes are supposed to connect with their client API socket.
     pub static ref CLIENT_PORTS: Vec<u64> = {
         NODES.iter().map(|pid| 8000 + pid).collect()
@@ -86,12 +156,30 @@ pub struct KeyValue {
     pub value: String,
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct NetworkState {
     pub(crate) nodes: Vec<u64>,
     pub(crate) alive_nodes: Vec<u64>,
     pub(crate) partitions: HashSet<(u64, u64)>,
     pub(crate) max_round: Option<Round>,
+    /// Latest status reported by each node.
+    pub(crate) node_status: HashMap<u64, NodeStatus>,
+    /// Nodes that are paused or whose client link is cut.
+    #[serde(default)]
+    pub(crate) freezes: Freezes,
+    /// Groups of the last `partition` command, if the links haven't changed since.
+    #[serde(default)]
+    pub(crate) partition_groups: Vec<Vec<u64>>,
+    /// Quorums the nodes can form with the current links.
+    #[serde(default)]
+    pub(crate) quorum: QuorumAnalysis,
+}
+
+impl NetworkState {
+    /// Whether the link between two nodes is cut in either direction.
+    pub(crate) fn is_cut(&self, a: u64, b: u64) -> bool {
+        self.partitions.contains(&(a, b)) || self.partitions.contains(&(b, a))
+    }
 }
 
 pub struct Coordinator {
@@ -99,9 +187,34 @@ pub struct Coordinator {
     io_sender: Sender<IOMessage>,
     op_sockets: Arc<Mutex<HashMap<u64, OwnedWriteHalf>>>,
     partitions: Arc<Mutex<HashSet<u64>>>,
+    /// Groups the network was last split into with a `partition` command.
+    partition_groups: Vec<Vec<u64>>,
+    /// Delay added to the messages sent from a port.
+    link_delays: Arc<Mutex<HashMap<u64, Duration>>>,
+    /// Bandwidth limits and queued messages of the links, by sending port.
+    link_queues: LinkQueues,
+    /// Whether the UI shows the queues of throttled links.
+    link_queues_shown: bool,
+    freezes: watch::Sender<Freezes>,
+    held_requests: HeldRequests,
     nodes: Vec<u64>,
     max_round: Arc<Mutex<Option<Round>>>,
-    cmd_queue: Arc<Mutex<VecDeque<(KVCommand, Option<u64>)>>>,
+    cmd_queue: CommandQueue,
+    node_status: HashMap<u64, NodeStatus>,
+    election_timeline: ElectionTimeline,
+    in_flight: SharedInFlight,
+    proposer_config: Arc<Mutex<ProposerConfig>>,
+    latency: LatencyRecorder,
+    downtime_monitor: DowntimeMonitor,
+    workload: Option<JoinHandle<()>>,
+    chaos: Option<JoinHandle<()>>,
+    recorder: Option<TrafficRecorder>,
+    inspector: Arc<Mutex<MessageInspector>>,
+    /// Rules dropping or delaying some types of messages.
+    filter: Arc<Mutex<MessageFilter>>,
+    replay: Option<(JoinHandle<()>, Sender<ReplayControl>)>,
+    /// Latest scans of the databases, if the UI shows the KV browser.
+    kv_browser: Option<KVBrowser>,
 }
 
 impl Coordinator {
@@ -111,36 +224,74 @@ impl Coordinator {
             io_sender,
             op_sockets: Arc::new(Mutex::new(HashMap::new())),
             partitions: Arc::new(Mutex::new(HashSet::new())),
+            partition_groups: vec![],
+            link_delays: Arc::new(Mutex::new(HashMap::new())),
+            link_queues: Arc::new(Mutex::new(HashMap::new())),
+            link_queues_shown: false,
+            freezes: watch::channel(Freezes::new()).0,
+            held_requests: Arc::new(Mutex::new(HashMap::new())),
             cmd_queue: Arc::new(Mutex::new(VecDeque::new())),
             nodes: vec![],
             max_round: Arc::new(Mutex::new(None)),
+            node_status: HashMap::new(),
+            election_timeline: ElectionTimeline::default(),
+            in_flight: Arc::new(Mutex::new(InFlight::default())),
+            proposer_config: Arc::new(Mutex::new(ProposerConfig::default())),
+            latency: LatencyRecorder::new(),
+            downtime_monitor: DowntimeMonitor::new(DOWNTIME_REPORT_FILE.clone()),
+            workload: None,
+            chaos: None,
+            recorder: None,
+            inspector: Arc::new(Mutex::new(MessageInspector::new(*INSPECT_MESSAGES))),
+            filter: Arc::new(Mutex::new(MessageFilter::default())),
+            replay: None,
+            kv_browser: None,
         }
     }
 
     async fn create_network_state(&self) -> NetworkState {
-        let partitions: HashSet<(u64, u64)> = self
-            .partitions
-            .lock()
-            .await
+        let partition_ports = self.partitions.lock().await.clone();
+        let partitions: HashSet<(u64, u64)> =
+            partition_ports.iter().map(port_to_connection).collect();
+        // the groups are outdated once links are cut or restored by other commands
+        let group_ports: HashSet<u64> = cut_links(&self.partition_groups)
+            .iter()
+            .map(|(from, to)| connection_to_port(from, to))
+            .collect();
+        let partition_groups = match group_ports == partition_ports {
+            true => self.partition_groups.clone(),
+            false => vec![],
+        };
+        let alive_nodes: Vec<u64> = self.op_sockets.lock().await.keys().copied().collect();
+        let freezes = self.freezes.borrow().clone();
+        let up: Vec<u64> = alive_nodes
             .iter()
-            .map(port_to_connection)
+            .copied()
+            .filter(|pid| !is_paused(&freezes, *pid))
             .collect();
+        let quorum = QuorumAnalysis::analyze(&self.nodes, &up, &partitions, *FLEX_QUORUM);
         NetworkState {
             nodes: self.nodes.clone(),
-            alive_nodes: self.op_sockets.lock().await.keys().copied().collect(),
+            alive_nodes,
             partitions,
             max_round: *self.max_round.lock().await,
+            node_status: self.node_status.clone(),
+            freezes,
+            partition_groups,
+            quorum,
         }
     }
 
     async fn create_omnipaxos_listeners(
         op_sockets: Arc<Mutex<HashMap<u64, OwnedWriteHalf>>>,
         sender: Sender<IOMessage>,
+        freezes: watch::Receiver<Freezes>,
     ) {
         // setup client sockets to talk to nodes
         for port in CLIENT_PORTS.iter() {
             let op_sockets = op_sockets.clone();
             let sender = sender.clone();
+            let mut freezes = freezes.clone();
 
             // Set up API sockets
             tokio::spawn(async move {
@@ -157,67 +308,189 @@ impl Coordinator {
                     )))
                     .await
                     .unwrap();
+                info!(pid = client_pid, "node joined");
                 // receiver actor
-                tokio::spawn(async move {
-                    let mut reader = BufReader::new(reader);
-                    loop {
-                        let mut data = vec![];
-                        let bytes_read = reader.read_until(b'\n', &mut data).await.unwrap();
-                        if bytes_read == 0 {
-                            // dropped socket EOF
-                            sender
-                                .send(IOMessage::UIMessage(UIMessage::OmnipaxosNodeCrashed(
-                                    client_pid,
-                                )))
-                                .await
-                                .unwrap();
-                            op_sockets.lock().await.remove(&client_pid);
-                            sender
-                                .send(IOMessage::CDMessage(CDMessage::OmnipaxosNodeCrashed(
-                                    client_pid,
-                                )))
-                                .await
-                                .unwrap();
-                            break;
-                        }
-                        if let Ok(msg) = serde_json::from_slice::<Message>(&data) {
-                            match msg {
-                                Message::APIResponse(APIResponse::NewRound(round), _pid) => sender
-                                    .send(IOMessage::CDMessage(CDMessage::NewRound(
-                                        client_pid, round,
+                tokio::spawn(
+                    async move {
+                        let mut reader = BufReader::new(reader);
+                        loop {
+                            let mut data = vec![];
+                            let bytes_read = reader.read_until(b'\n', &mut data).await.unwrap();
+                            if bytes_read == 0 {
+                                // dropped socket EOF
+                                warn!("node crashed");
+                                sender
+                                    .send(IOMessage::UIMessage(UIMessage::OmnipaxosNodeCrashed(
+                                        client_pid,
                                     )))
                                     .await
-                                    .unwrap(),
-                                Message::APIResponse(response, pid) => sender
-                                    .send(IOMessage::UIMessage(UIMessage::OmnipaxosResponse(
-                                        response, pid,
+                                    .unwrap();
+                                op_sockets.lock().await.remove(&client_pid);
+                                sender
+                                    .send(IOMessage::CDMessage(CDMessage::OmnipaxosNodeCrashed(
+                                        client_pid,
                                     )))
                                     .await
-                                    .unwrap(),
-                                _ => (),
+                                    .unwrap();
+                                break;
+                            }
+                            // hold the responses of a paused node and drop them if its client
+                            // link is cut
+                            let freeze = freezes
+                                .wait_for(|f| !is_paused(f, client_pid))
+                                .await
+                                .map(|f| f.get(&client_pid).copied());
+                            if let Ok(Some(Freeze::ClientIsolated)) = freeze {
+                                continue;
+                            }
+                            if let Ok(msg) = serde_json::from_slice::<Message>(&data) {
+                                match msg {
+                                    Message::APIResponse(APIResponse::NewRound(round), _pid) => {
+                                        debug!(
+                                            ballot = round.map(|r| r.round_num),
+                                            leader = round.map(|r| r.leader),
+                                            "new round"
+                                        );
+                                        sender
+                                            .send(IOMessage::CDMessage(CDMessage::NewRound(
+                                                client_pid, round,
+                                            )))
+                                            .await
+                                            .unwrap()
+                                    }
+                                    Message::APIResponse(APIResponse::Scan(entries), _pid) => {
+                                        sender
+                                            .send(IOMessage::CDMessage(CDMessage::ScanResponse(
+                                                client_pid, entries,
+                                            )))
+                                            .await
+                                            .unwrap()
+                                    }
+                                    Message::APIResponse(
+                                        APIResponse::DecidedRequests(request_ids),
+                                        _pid,
+                                    ) => sender
+                                        .send(IOMessage::CDMessage(CDMessage::DecidedRequests(
+                                            client_pid,
+                                            request_ids,
+                                        )))
+                                        .await
+                                        .unwrap(),
+                                    // Shown by the coordinator unless a workload sent the read
+                                    Message::APIResponse(
+                                        APIResponse::Get(key, value, request_id),
+                                        _pid,
+                                    ) => sender
+                                        .send(IOMessage::CDMessage(CDMessage::ReadResponse(
+                                            client_pid, key, value, request_id,
+                                        )))
+                                        .await
+                                        .unwrap(),
+                                    Message::APIResponse(APIResponse::Status(status), _pid) => {
+                                        sender
+                                            .send(IOMessage::CDMessage(CDMessage::NodeStatus(
+                                                client_pid, status,
+                                            )))
+                                            .await
+                                            .unwrap()
+                                    }
+                                    Message::APIResponse(response, pid) => {
+                                        if let APIResponse::Decided(idx) = response {
+                                            debug!(decided_idx = idx, "decided");
+                                            sender
+                                                .send(IOMessage::CDMessage(CDMessage::Decided(
+                                                    client_pid, idx,
+                                                )))
+                                                .await
+                                                .unwrap();
+                                        }
+                                        sender
+                                            .send(IOMessage::UIMessage(
+                                                UIMessage::OmnipaxosResponse(response, pid),
+                                            ))
+                                            .await
+                                            .unwrap()
+                                    }
+                                    _ => (),
+                                }
                             }
                         }
                     }
-                });
+                    .instrument(info_span!("api_link", pid = client_pid)),
+                );
             });
         }
     }
 
-    async fn create_network_actor(partitions: Arc<Mutex<HashSet<u64>>>) {
-        // setup intra-cluster communication
+    async fn create_network_actor(
+        partitions: Arc<Mutex<HashSet<u64>>>,
+        link_delays: Arc<Mutex<HashMap<u64, Duration>>>,
+        link_queues: LinkQueues,
+        recorder: Option<TrafficRecorder>,
+        inspector: Arc<Mutex<MessageInspector>>,
+        filter: Arc<Mutex<MessageFilter>>,
+        freezes: watch::Receiver<Freezes>,
+    ) {
+        // setup intra-cluster communication. The channels are bounded, so a node that reads
+        // slower than its links release messages holds them in the link queues instead of losing
+        // them, which keeps the recording and the message flow complete.
         let mut out_channels = HashMap::new();
+        let mut out_receivers = HashMap::new();
         for port in PORT_MAPPINGS.keys() {
-            let (sender, _rec) = broadcast::channel::<Vec<u8>>(10000);
-            let sender = Arc::new(sender);
-            out_channels.insert(*port, sender.clone());
+            let (sender, receiver) = mpsc::channel::<Vec<u8>>(10000);
+            out_channels.insert(*port, sender);
+            out_receivers.insert(*port, receiver);
+        }
+
+        // messages wait in a queue per link until their delay is over and the link has the
+        // bandwidth for them, which keeps them in order
+        let mut delay_queues = HashMap::new();
+        for port in PORT_MAPPINGS.keys() {
+            let (queue_sender, mut queue_receiver) =
+                mpsc::unbounded_channel::<(Instant, Vec<u8>)>();
+            let out_channel = out_channels.get(port).unwrap().clone();
+            let from_port = *PORT_MAPPINGS.get(port).unwrap();
+            let link_queues = link_queues.clone();
+            let mut freezes = freezes.clone();
+            let (from, to) = (
+                *PORT_TO_PID_MAPPING.get(&from_port).unwrap(),
+                *PORT_TO_PID_MAPPING.get(port).unwrap(),
+            );
+            tokio::spawn(async move {
+                while let Some((deliver_at, msg)) = queue_receiver.recv().await {
+                    if deliver_at > Instant::now() {
+                        tokio::time::sleep_until(deliver_at).await;
+                    }
+                    // hold the message while either node is paused
+                    _ = freezes
+                        .wait_for(|f| !is_paused(f, from) && !is_paused(f, to))
+                        .await;
+                    let wait = link_queues
+                        .lock()
+                        .await
+                        .get_mut(&from_port)
+                        .and_then(|queue| queue.bucket.as_mut())
+                        .map(|bucket| bucket.reserve(msg.len()));
+                    if let Some(wait) = wait.filter(|w| !w.is_zero()) {
+                        tokio::time::sleep(wait).await;
+                    }
+                    if let Some(queue) = link_queues.lock().await.get_mut(&from_port) {
+                        queue.messages = queue.messages.saturating_sub(1);
+                        queue.bytes = queue.bytes.saturating_sub(msg.len());
+                    }
+                    if out_channel.send(msg).await.is_err() {
+                        break;
+                    }
+                }
+            });
+            delay_queues.insert(*port, queue_sender);
         }
-        let out_channels = Arc::new(out_channels);
 
         let (central_sender, mut central_receiver) = mpsc::channel(10000);
         let central_sender = Arc::new(central_sender);
 
         for port in PORT_MAPPINGS.keys() {
-            let out_chans = out_channels.clone();
+            let mut out_receiver = out_receivers.remove(port).unwrap();
             let central_sender = central_sender.clone();
             tokio::spawn(async move {
                 let central_sender = central_sender.clone();
@@ -227,10 +500,8 @@ impl Coordinator {
                 let (socket, _addr) = listener.accept().await.unwrap();
                 let (reader, mut writer) = socket.into_split();
                 // sender actor
-                let out_channels = out_chans.clone();
                 tokio::spawn(async move {
-                    let mut receiver = out_channels.get(port).unwrap().clone().subscribe();
-                    while let Ok(data) = receiver.recv().await {
+                    while let Some(data) = out_receiver.recv().await {
                         let _ = writer.write_all(&data).await;
                     }
                 });
@@ -240,7 +511,11 @@ impl Coordinator {
                     let mut reader = BufReader::new(reader);
                     loop {
                         let mut data = vec![];
-                        reader.read_until(b'\n', &mut data).await.unwrap();
+                        let bytes_read = reader.read_until(b'\n', &mut data).await.unwrap();
+                        if bytes_read == 0 {
+                            // dropped socket EOF
+                            break;
+                        }
                         _ = central_sender
                             .send((port, PORT_MAPPINGS.get(port).unwrap(), data))
                             .await
@@ -250,100 +525,645 @@ impl Coordinator {
         }
 
         // the one central actor that sees all messages
-        tokio::spawn(async move {
-            while let Some((from_port, to_port, msg)) = central_receiver.recv().await {
-                // drop message if network is partitioned between sender and receiver
-                let nodes_are_connected = !partitions.lock().await.contains(from_port);
-                if nodes_are_connected {
-                    let sender = out_channels.get(to_port).unwrap().clone();
-                    _ = sender.send(msg);
+        tokio::spawn(
+            async move {
+                while let Some((from_port, to_port, msg)) = central_receiver.recv().await {
+                    // drop message if network is partitioned between sender and receiver
+                    let nodes_are_connected = !partitions.lock().await.contains(from_port);
+                    let delay = link_delays
+                        .lock()
+                        .await
+                        .get(from_port)
+                        .copied()
+                        .unwrap_or_default();
+                    let from = *PORT_TO_PID_MAPPING.get(from_port).unwrap();
+                    let to = *PORT_TO_PID_MAPPING.get(to_port).unwrap();
+                    let mut inspector = inspector.lock().await;
+                    let mut filter = filter.lock().await;
+                    let kind = (inspector.enabled || !filter.is_empty()).then(|| classify(&msg));
+                    // drop or delay the message if a filter rule matches its type
+                    let mut forwarded = nodes_are_connected;
+                    let mut filter_delay = Duration::ZERO;
+                    if let (true, Some(kind)) = (nodes_are_connected, kind) {
+                        match filter.apply(from, to, kind) {
+                            Some(FilterAction::Drop) => forwarded = false,
+                            Some(FilterAction::Delay(extra)) => filter_delay = extra,
+                            None => (),
+                        }
+                    }
+                    drop(filter);
+                    if let (true, Some(kind)) = (inspector.enabled, kind) {
+                        inspector.count(from, to, kind, !forwarded);
+                    }
+                    drop(inspector);
+                    trace!(
+                        from,
+                        to,
+                        bytes = msg.len(),
+                        forwarded,
+                        delay_ms = (delay + filter_delay).as_millis() as u64,
+                    );
+                    if let Some(recorder) = &recorder {
+                        let verdict = match forwarded {
+                            true => Verdict::Forwarded,
+                            false => Verdict::Dropped,
+                        };
+                        let delay_ms = (delay + filter_delay).as_millis() as u64;
+                        recorder.message(from, to, msg.len(), verdict, delay_ms, kind);
+                    }
+                    if forwarded {
+                        let mut link_queues = link_queues.lock().await;
+                        let link_queue = link_queues.entry(*from_port).or_default();
+                        link_queue.messages += 1;
+                        link_queue.bytes += msg.len();
+                        drop(link_queues);
+                        let queue = delay_queues.get(to_port).unwrap();
+                        queue_delayed(queue, msg, delay, filter_delay);
+                    }
                 }
             }
-        });
+            .instrument(info_span!("proxy")),
+        );
     }
 
     pub async fn run(&mut self) {
-        while let Some(m) = self.receiver.recv().await {
-            match m {
-                CDMessage::Initialize => {
-                    self.nodes = CLIENT_PORTS
-                        .iter()
-                        .map(|port| *PORT_TO_PID_MAPPING.get(port).unwrap())
-                        .collect();
-                    let mut proposer = ProposalStreamer::new(
-                        self.io_sender.clone(),
-                        self.op_sockets.clone(),
-                        self.cmd_queue.clone(),
-                        self.max_round.clone(),
-                    );
-                    tokio::spawn(async move { proposer.run().await });
-
-                    let op_sockets = self.op_sockets.clone();
-                    let io_sender = self.io_sender.clone();
-                    let partitions = self.partitions.clone();
-                    join!(
-                        Coordinator::create_omnipaxos_listeners(op_sockets, io_sender),
-                        Coordinator::create_network_actor(partitions),
-                    );
+        let mut downtime_interval = tokio::time::interval(DOWNTIME_CHECK_RATE);
+        let mut latency_interval = tokio::time::interval(LATENCY_REPORT_RATE);
+        let mut timeout_interval = tokio::time::interval(PROPOSAL_TIMEOUT_CHECK_RATE);
+        let mut message_flow_interval = tokio::time::interval(MESSAGE_FLOW_REPORT_RATE);
+        let mut link_queue_interval = tokio::time::interval(LINK_QUEUE_REPORT_RATE);
+        let mut kv_scan_interval = tokio::time::interval(KV_SCAN_RATE);
+        loop {
+            tokio::select! {
+                m = self.receiver.recv() => match m {
+                    Some(m) => self.handle(m).await,
+                    // All senders are gone, nothing can reach the coordinator anymore
+                    None => break,
+                },
+                _ = downtime_interval.tick() => self.check_downtime().await,
+                _ = latency_interval.tick() => self.report_latency().await,
+                _ = timeout_interval.tick() => self.check_proposal_timeouts().await,
+                _ = message_flow_interval.tick() => self.report_message_flow().await,
+                _ = link_queue_interval.tick() => self.report_link_queues().await,
+                _ = kv_scan_interval.tick() => self.scan_databases().await,
+            }
+        }
+    }
+
+    async fn handle(&mut self, m: CDMessage) {
+        match m {
+            CDMessage::Initialize => {
+                self.nodes = CLIENT_PORTS
+                    .iter()
+                    .map(|port| *PORT_TO_PID_MAPPING.get(port).unwrap())
+                    .collect();
+                let mut proposer = ProposalStreamer::new(
+                    self.io_sender.clone(),
+                    self.op_sockets.clone(),
+                    self.cmd_queue.clone(),
+                    self.max_round.clone(),
+                    self.in_flight.clone(),
+                    self.proposer_config.clone(),
+                    RequestGate::new(self.freezes.subscribe(), self.held_requests.clone()),
+                );
+                tokio::spawn(async move { proposer.run().await });
+
+                if let Some(path) = TRAFFIC_RECORD_FILE.as_ref() {
+                    match TrafficRecorder::new(path) {
+                        Ok(recorder) => {
+                            info!(path, "recording traffic");
+                            self.recorder = Some(recorder);
+                        }
+                        Err(e) => {
+                            self.send_to_ui(UIMessage::Debug(format!(
+                                "Failed to record traffic to {path}: {e}"
+                            )))
+                            .await
+                        }
+                    }
                 }
-                CDMessage::KVCommand(command, pid) => {
-                    self.cmd_queue.lock().await.push_front((command, pid))
+
+                let op_sockets = self.op_sockets.clone();
+                let io_sender = self.io_sender.clone();
+                let partitions = self.partitions.clone();
+                let link_delays = self.link_delays.clone();
+                let link_queues = self.link_queues.clone();
+                let recorder = self.recorder.clone();
+                let inspector = self.inspector.clone();
+                let filter = self.filter.clone();
+                join!(
+                    Coordinator::create_omnipaxos_listeners(
+                        op_sockets,
+                        io_sender,
+                        self.freezes.subscribe()
+                    ),
+                    Coordinator::create_network_actor(
+                        partitions,
+                        link_delays,
+                        link_queues,
+                        recorder,
+                        inspector,
+                        filter,
+                        self.freezes.subscribe()
+                    ),
+                );
+            }
+            CDMessage::SetInspection(enabled) => {
+                self.inspector.lock().await.set_enabled(enabled);
+                info!(enabled, "message inspection");
+                if !enabled {
+                    self.send_to_ui(UIMessage::MessageFlow(None)).await;
                 }
-                CDMessage::SetConnection(from, to, is_connected) => {
-                    if !self.nodes.contains(&from) {
-                        self.send_to_ui(UIMessage::NoSuchNode(from, self.nodes.clone()))
-                            .await;
-                    } else if to.is_some() && !self.nodes.contains(&to.unwrap()) {
-                        self.send_to_ui(UIMessage::NoSuchNode(to.unwrap(), self.nodes.clone()))
-                            .await;
-                    } else {
-                        match to {
-                            Some(to) => {
-                                self.set_partition(from, to, is_connected).await;
-                                self.send_network_update().await;
-                            }
-                            None => {
-                                let other_nodes = self.nodes.iter().filter(|&&n| n != from);
-                                for to in other_nodes {
-                                    self.set_partition(from, *to, is_connected).await;
-                                }
-                                self.send_network_update().await;
-                            }
-                        }
+            }
+            CDMessage::Partition(groups) => self.partition(groups).await,
+            CDMessage::Heal => {
+                self.heal().await;
+                info!("network healed");
+                self.send_network_update().await;
+                self.send_to_ui(UIMessage::Debug("Restored all links".to_string()))
+                    .await;
+            }
+            CDMessage::ClearDelays => {
+                self.clear_delays().await;
+                info!("link delays removed");
+                self.send_network_update().await;
+                self.send_to_ui(UIMessage::Debug("Removed all link delays".to_string()))
+                    .await;
+            }
+            CDMessage::SetFreeze(pid, freeze) => self.set_freeze(pid, freeze).await,
+            CDMessage::SetBandwidth(from, to, rate) => self.set_bandwidth(from, to, rate).await,
+            CDMessage::AddFilter(rule) => self.add_filter(rule).await,
+            CDMessage::RemoveFilter(id) => {
+                let output = match self.filter.lock().await.remove(id) {
+                    Some(rule) => {
+                        info!(id, %rule, "filter removed");
+                        format!("Removed filter #{id}: {rule}")
                     }
+                    None => format!("There is no filter #{id}"),
+                };
+                self.send_to_ui(UIMessage::Debug(output)).await;
+            }
+            CDMessage::ClearFilters => {
+                self.filter.lock().await.clear();
+                info!("filters cleared");
+                self.send_to_ui(UIMessage::Debug("Removed all filters".to_string()))
+                    .await;
+            }
+            CDMessage::BrowseKeys(browse) => {
+                self.kv_browser = match browse {
+                    true => self
+                        .kv_browser
+                        .take()
+                        .or(Some(KVBrowser::new(KV_SCAN_MAX_KEYS))),
+                    false => None,
+                };
+            }
+            CDMessage::ScanResponse(pid, entries) => {
+                if let Some(kv_browser) = self.kv_browser.as_mut() {
+                    kv_browser.record_scan(pid, entries);
                 }
-                CDMessage::OmnipaxosNodeCrashed(_pid) => {
-                    self.send_network_update().await;
+            }
+            CDMessage::ListFilters => {
+                let rules = self.filter.lock().await.describe();
+                if rules.is_empty() {
+                    self.send_to_ui(UIMessage::Debug("No filters".to_string()))
+                        .await;
                 }
-                CDMessage::OmnipaxosNodeJoined(_pid) => {
-                    self.send_network_update().await;
+                for rule in rules {
+                    self.send_to_ui(UIMessage::Debug(rule)).await;
                 }
-                CDMessage::StartBatchingPropose(num) => self.batch_proposals(num).await,
-                CDMessage::NewRound(_client_pid, new_round) => {
-                    let mut curr_round = self.max_round.lock().await;
-                    match (*curr_round, new_round) {
-                        (Some(old_round), Some(round)) if old_round < round => {
-                            *curr_round = Some(round);
-                            drop(curr_round);
+            }
+            CDMessage::StartReplay(path) => self.start_replay(path).await,
+            CDMessage::ReplayControl(control) => match &self.replay {
+                Some((_, controls)) => controls.send(control).await.unwrap(),
+                None => {
+                    self.send_to_ui(UIMessage::Debug(
+                        "No trace is being replayed, start the app with: app replay <trace-file>"
+                            .to_string(),
+                    ))
+                    .await
+                }
+            },
+            CDMessage::KVCommand(command, pid) => {
+                self.cmd_queue
+                    .lock()
+                    .await
+                    .push_front((command, pid, CommandSource::User))
+            }
+            CDMessage::SetConnection(from, to, is_connected) => {
+                if !self.nodes.contains(&from) {
+                    self.send_to_ui(UIMessage::NoSuchNode(from, self.nodes.clone()))
+                        .await;
+                } else if to.is_some() && !self.nodes.contains(&to.unwrap()) {
+                    self.send_to_ui(UIMessage::NoSuchNode(to.unwrap(), self.nodes.clone()))
+                        .await;
+                } else {
+                    match to {
+                        Some(to) => {
+                            self.set_partition(from, to, is_connected).await;
                             self.send_network_update().await;
                         }
-                        (None, Some(round)) => {
-                            *curr_round = Some(round);
-                            drop(curr_round);
+                        None => {
+                            let other_nodes = self.nodes.iter().filter(|&&n| n != from);
+                            for to in other_nodes {
+                                self.set_partition(from, *to, is_connected).await;
+                            }
                             self.send_network_update().await;
                         }
-                        _ => (),
                     }
                 }
-                CDMessage::Scenario(scenario_type) => {
-                    assert!(
-                        self.nodes.len() == 5,
-                        "Must have 5 nodes to execute scenarios"
-                    );
-                    self.handle_scenario(scenario_type).await;
+            }
+            CDMessage::OmnipaxosNodeCrashed(pid) => {
+                self.node_status.remove(&pid);
+                if let Some(kv_browser) = self.kv_browser.as_mut() {
+                    kv_browser.forget(pid);
+                }
+                self.send_network_update().await;
+            }
+            CDMessage::OmnipaxosNodeJoined(_pid) => {
+                self.send_network_update().await;
+            }
+            CDMessage::StartBatchingPropose(num) => self.batch_proposals(num).await,
+            CDMessage::StartWorkload(spec) => self.start_workload(spec).await,
+            CDMessage::StopWorkload => {
+                if let Some(workload) = self.workload.take() {
+                    workload.abort();
+                    // Commands typed by the user stay queued
+                    self.cmd_queue
+                        .lock()
+                        .await
+                        .retain(|(_, _, source)| *source != CommandSource::Workload);
+                    self.send_to_ui(UIMessage::WorkloadStopped).await;
+                }
+            }
+            CDMessage::StartChaos(config) => self.start_chaos(config).await,
+            CDMessage::StopChaos => {
+                if let Some(chaos) = self.chaos.take() {
+                    chaos.abort();
+                    info!("chaos stopped");
+                    self.heal().await;
+                    self.clear_delays().await;
+                    self.send_network_update().await;
+                    self.send_to_ui(UIMessage::ChaosStopped).await;
+                }
+            }
+            CDMessage::ApplyChaos(step, action) => self.apply_chaos(step, action).await,
+            CDMessage::NewRound(client_pid, new_round) => {
+                let mut curr_round = self.max_round.lock().await;
+                match (*curr_round, new_round) {
+                    (Some(old_round), Some(round)) if old_round < round => {
+                        info!(
+                            pid = client_pid,
+                            ballot = round.round_num,
+                            leader = round.leader,
+                            old_leader = old_round.leader,
+                            "leader changed"
+                        );
+                        *curr_round = Some(round);
+                        drop(curr_round);
+                        self.record_round_change(Some(old_round), round).await;
+                        self.retry_proposals().await;
+                        self.send_network_update().await;
+                    }
+                    (None, Some(round)) => {
+                        info!(
+                            pid = client_pid,
+                            ballot = round.round_num,
+                            leader = round.leader,
+                            "leader elected"
+                        );
+                        *curr_round = Some(round);
+                        drop(curr_round);
+                        self.record_round_change(None, round).await;
+                        self.retry_proposals().await;
+                        self.send_network_update().await;
+                    }
+                    _ => (),
+                }
+            }
+            CDMessage::Decided(pid, decided_idx) => {
+                if let Some(recorder) = &mut self.recorder {
+                    recorder.decided(pid, decided_idx);
+                }
+                let first_write_sent = self.in_flight.lock().await.first_write_in_round();
+                if self
+                    .election_timeline
+                    .handle_decided(decided_idx, first_write_sent)
+                {
+                    self.send_election_timeline().await;
+                }
+            }
+            CDMessage::DecidedRequests(_pid, request_ids) => {
+                let latencies = self.in_flight.lock().await.handle_decided(&request_ids);
+                for latency in latencies {
+                    self.latency.record(latency);
+                }
+            }
+            CDMessage::ReadResponse(pid, key, value, request_id) => {
+                let acked = match request_id {
+                    Some(id) => self.in_flight.lock().await.handle_read(id, value.clone()),
+                    None => None,
+                };
+                if let Some((latency, _)) = acked {
+                    self.latency.record(latency);
+                }
+                // The replies to the reads of a workload would flood the output
+                if !matches!(acked, Some((_, CommandSource::Workload))) {
+                    self.send_to_ui(UIMessage::OmnipaxosResponse(
+                        APIResponse::Get(key, value, request_id),
+                        pid,
+                    ))
+                    .await;
+                }
+            }
+            CDMessage::CheckHistory => self.check_history().await,
+            CDMessage::ClearHistory => {
+                self.in_flight.lock().await.clear_history();
+                self.send_to_ui(UIMessage::HistoryCleared).await;
+            }
+            CDMessage::SetProposer(setting) => {
+                let mut config = self.proposer_config.lock().await;
+                config.apply(setting);
+                info!(%config, "proposer configured");
+                let output = format!("Proposer: {config}");
+                drop(config);
+                self.send_to_ui(UIMessage::Debug(output)).await;
+            }
+            CDMessage::NodeStatus(pid, status) => {
+                self.node_status.insert(pid, status);
+                self.send_network_update().await;
+            }
+            CDMessage::Scenario(scenario_type) => {
+                assert!(
+                    self.nodes.len() == SCENARIO_NODES,
+                    "Must have {SCENARIO_NODES} nodes to execute scenarios"
+                );
+                self.handle_scenario(scenario_type).await;
+            }
+        }
+    }
+
+    async fn check_downtime(&mut self) {
+        let (pending, proposed, attempts) = {
+            let queued = !self.cmd_queue.lock().await.is_empty();
+            let in_flight = self.in_flight.lock().await;
+            (
+                queued || in_flight.outstanding_writes() > 0,
+                in_flight.proposed_writes(),
+                in_flight.write_attempts(),
+            )
+        };
+        let ended_window = self.downtime_monitor.check(
+            self.election_timeline.decided_idx(),
+            proposed,
+            attempts,
+            pending,
+            self.election_timeline.records(),
+        );
+        if let Some(window) = ended_window {
+            info!(
+                duration_ms = window.duration.as_millis() as u64,
+                ballot = window.ended_by.map(|r| r.round_num),
+                leader = window.ended_by.map(|r| r.leader),
+                proposed = window.proposed,
+                attempts = window.attempts,
+                decided = window.decided,
+                "downtime window ended"
+            );
+            let report_result = self.downtime_monitor.write_report();
+            self.send_to_ui(UIMessage::Downtime(window)).await;
+            if let Err(e) = report_result {
+                self.send_to_ui(UIMessage::Debug(format!(
+                    "Failed to write downtime report to {}: {e}",
+                    self.downtime_monitor.report_file()
+                )))
+                .await;
+            }
+        }
+    }
+
+    async fn check_proposal_timeouts(&mut self) {
+        let timeout = self.proposer_config.lock().await.timeout;
+        let timed_out = self.in_flight.lock().await.take_timed_out(timeout);
+        if !timed_out.is_empty() {
+            warn!(count = timed_out.len(), "proposals timed out");
+            let failures = timed_out
+                .into_iter()
+                .map(|c| FailedProposal::new(c, FailureReason::Timeout(timeout)))
+                .collect();
+            self.send_to_ui(UIMessage::ProposalsFailed(failures)).await;
+        }
+    }
+
+    /// Sends the unacknowledged routed proposals again, since they may have been lost with the
+    /// old leader.
+    async fn retry_proposals(&self) {
+        let retried = self.in_flight.lock().await.retry_routed();
+        if retried > 0 {
+            info!(count = retried, "retrying proposals");
+            self.send_to_ui(UIMessage::ProposalsRetried(retried)).await;
+        }
+    }
+
+    /// Checks the recorded history for linearizability in the background and reports the result
+    /// to the UI.
+    async fn check_history(&self) {
+        let history = self.in_flight.lock().await.history().operations().to_vec();
+        let io_sender = self.io_sender.clone();
+        tokio::spawn(async move {
+            let report = tokio::task::spawn_blocking(move || linearizability::check(&history))
+                .await
+                .unwrap();
+            info!(
+                operations = report.operations,
+                keys = report.keys,
+                outcome = ?report.outcome,
+                "linearizability check finished"
+            );
+            io_sender
+                .send(IOMessage::UIMessage(UIMessage::LinearizabilityCheck(
+                    report,
+                )))
+                .await
+                .unwrap();
+        });
+    }
+
+    /// Cuts all links between nodes of different groups in one update, so that the proxy never
+    /// forwards messages over a partially applied split.
+    async fn partition(&mut self, groups: Vec<Vec<u64>>) {
+        if let Some(&pid) = groups
+            .iter()
+            .flatten()
+            .find(|pid| !self.nodes.contains(pid))
+        {
+            self.send_to_ui(UIMessage::NoSuchNode(pid, self.nodes.clone()))
+                .await;
+            return;
+        }
+        let Some(groups) = complete_groups(groups, &self.nodes) else {
+            self.send_to_ui(UIMessage::Debug(
+                "Invalid partition: a node can only be in one group".to_string(),
+            ))
+            .await;
+            return;
+        };
+        self.replace_partitions(&cut_links(&groups)).await;
+        let groups_str = format_groups(&groups);
+        info!(groups = groups_str, "network partitioned");
+        self.partition_groups = groups;
+        self.send_network_update().await;
+        self.send_to_ui(UIMessage::Debug(format!(
+            "Partitioned the network into {groups_str}"
+        )))
+        .await;
+    }
+
+    async fn set_freeze(&mut self, pid: u64, freeze: Option<Freeze>) {
+        if !self.nodes.contains(&pid) {
+            self.send_to_ui(UIMessage::NoSuchNode(pid, self.nodes.clone()))
+                .await;
+            return;
+        }
+        // the proposer checks whether a node is paused with the sockets locked
+        let mut op_sockets = self.op_sockets.lock().await;
+        self.freezes.send_modify(|freezes| match freeze {
+            Some(freeze) => _ = freezes.insert(pid, freeze),
+            None => _ = freezes.remove(&pid),
+        });
+        if freeze != Some(Freeze::Paused) {
+            let held = self.held_requests.lock().await.remove(&pid);
+            if let (None, Some(held), Some(writer)) = (freeze, held, op_sockets.get_mut(&pid)) {
+                if let Err(e) = writer.write_all(&held).await {
+                    warn!(pid, error = %e, "failed to send held requests");
                 }
             }
         }
+        drop(op_sockets);
+        let output = match freeze {
+            Some(freeze) => {
+                info!(pid, %freeze, "node frozen");
+                match freeze {
+                    Freeze::Paused => format!("Paused node {pid}"),
+                    Freeze::ClientIsolated => format!("Cut the client link of node {pid}"),
+                }
+            }
+            None => {
+                info!(pid, "node resumed");
+                format!("Resumed node {pid}")
+            }
+        };
+        self.send_to_ui(UIMessage::Debug(output)).await;
+        self.send_network_update().await;
+    }
+
+    async fn set_bandwidth(&mut self, from: u64, to: u64, rate: Option<u64>) {
+        if let Some(&pid) = [from, to].iter().find(|pid| !self.nodes.contains(pid)) {
+            self.send_to_ui(UIMessage::NoSuchNode(pid, self.nodes.clone()))
+                .await;
+            return;
+        }
+        let port = connection_to_port(&from, &to);
+        let mut link_queues = self.link_queues.lock().await;
+        link_queues.entry(port).or_default().bucket = rate.map(TokenBucket::new);
+        drop(link_queues);
+        let output = match rate {
+            Some(rate) => {
+                info!(from, to, bytes_per_sec = rate, "link throttled");
+                format!(
+                    "Limited the link from node {from} to node {to} to {}",
+                    format_bandwidth(rate)
+                )
+            }
+            None => {
+                info!(from, to, "link bandwidth limit removed");
+                format!("Removed the bandwidth limit from node {from} to node {to}")
+            }
+        };
+        self.send_to_ui(UIMessage::Debug(output)).await;
+    }
+
+    /// Sends the queues of the throttled links to the UI.
+    async fn report_link_queues(&mut self) {
+        let mut statuses: Vec<LinkQueueStatus> = self
+            .link_queues
+            .lock()
+            .await
+            .iter()
+            .filter_map(|(port, queue)| {
+                let bucket = queue.bucket.as_ref()?;
+                Some(LinkQueueStatus {
+                    from: *PORT_TO_PID_MAPPING.get(port).unwrap(),
+                    to: *PORT_TO_PID_MAPPING
+                        .get(PORT_MAPPINGS.get(port).unwrap())
+                        .unwrap(),
+                    bandwidth: bucket.rate(),
+                    messages: queue.messages,
+                    bytes: queue.bytes,
+                })
+            })
+            .collect();
+        if statuses.is_empty() && !self.link_queues_shown {
+            return;
+        }
+        statuses.sort_by_key(|s| (s.from, s.to));
+        self.link_queues_shown = !statuses.is_empty();
+        self.send_to_ui(UIMessage::LinkQueues(statuses)).await;
+    }
+
+    async fn add_filter(&mut self, rule: FilterRule) {
+        let unknown = [rule.from, rule.to]
+            .into_iter()
+            .flatten()
+            .find(|pid| !self.nodes.contains(pid));
+        if let Some(pid) = unknown {
+            self.send_to_ui(UIMessage::NoSuchNode(pid, self.nodes.clone()))
+                .await;
+            return;
+        }
+        let id = self.filter.lock().await.add(rule);
+        info!(id, %rule, "filter added");
+        self.send_to_ui(UIMessage::Debug(format!("Added filter #{id}: {rule}")))
+            .await;
+    }
+
+    /// Shows the last scans of the databases and scans them again.
+    async fn scan_databases(&mut self) {
+        let Some(kv_browser) = self.kv_browser.as_mut() else {
+            return;
+        };
+        let comparison = kv_browser.compare();
+        let mut data = serde_json::to_vec(&Message::ScanRequest(kv_browser.limit())).unwrap();
+        data.push(b'\n');
+        let frozen: Vec<u64> = self.freezes.borrow().keys().copied().collect();
+        let mut op_sockets = self.op_sockets.lock().await;
+        for (pid, writer) in op_sockets.iter_mut() {
+            if frozen.contains(pid) {
+                kv_browser.mark_stale(*pid);
+                continue;
+            }
+            if let Err(e) = writer.write_all(&data).await {
+                warn!(pid, error = %e, "failed to send scan request");
+            }
+        }
+        drop(op_sockets);
+        self.send_to_ui(UIMessage::KVComparison(comparison)).await;
+    }
+
+    async fn report_message_flow(&self) {
+        let mut inspector = self.inspector.lock().await;
+        if inspector.enabled {
+            let report = inspector.report();
+            drop(inspector);
+            self.send_to_ui(UIMessage::MessageFlow(Some(report))).await;
+        }
+    }
+
+    async fn report_latency(&mut self) {
+        let report = self.latency.report();
+        if report.interval.is_some() {
+            self.send_to_ui(UIMessage::Latency(report)).await;
+        }
     }
 
     async fn send_to_ui(&self, msg: UIMessage) {
@@ -355,6 +1175,9 @@ impl Coordinator {
 
     async fn send_network_update(&self) {
         let cluster = self.create_network_state().await;
+        if let Some(recorder) = &self.recorder {
+            recorder.network(&cluster);
+        }
         self.io_sender
             .send(IOMessage::UIMessage(UIMessage::OmnipaxosNetworkUpdate(
                 cluster,
@@ -363,6 +1186,24 @@ impl Coordinator {
             .unwrap();
     }
 
+    async fn record_round_change(&mut self, old_round: Option<Round>, round: Round) {
+        let partitions = self.create_network_state().await.partitions;
+        let oldest_pending = {
+            let mut in_flight = self.in_flight.lock().await;
+            in_flight.round_changed();
+            in_flight.oldest_pending_write()
+        };
+        self.election_timeline
+            .record_round_change(old_round, round, &partitions, oldest_pending);
+        self.send_election_timeline().await;
+    }
+
+    async fn send_election_timeline(&self) {
+        let records = self.election_timeline.records().to_vec();
+        self.send_to_ui(UIMessage::ElectionTimeline(records)).await;
+    }
+
+    #[instrument(name = "partition", skip(self))]
     async fn set_partition(&self, from: u64, to: u64, is_connected: bool) {
         // UI can only display undirected connections, so we add partitions in both
         // connection directions
@@ -377,6 +1218,7 @@ impl Coordinator {
             partitions.insert(from_port);
             partitions.insert(to_port);
         }
+        debug!("connection updated");
     }
 
     async fn batch_proposals(&self, num: u64) {
@@ -386,11 +1228,130 @@ impl Coordinator {
                 key: BATCH_KEY.to_string(),
                 value: random::<u64>().to_string(),
             });
-            cmd_queue.push_front((cmd, None));
+            cmd_queue.push_front((cmd, None, CommandSource::User));
+        }
+    }
+
+    async fn start_workload(&mut self, spec: WorkloadSpec) {
+        if let Some(workload) = self.workload.take() {
+            workload.abort();
+        }
+        info!(?spec, "starting workload");
+        self.send_to_ui(UIMessage::WorkloadStarted(spec.describe()))
+            .await;
+        let mut runner = WorkloadRunner::new(spec, self.io_sender.clone(), self.cmd_queue.clone());
+        self.workload = Some(tokio::spawn(async move { runner.run().await }));
+    }
+
+    async fn start_replay(&mut self, path: String) {
+        let events = match traffic::read_trace(&path) {
+            Ok(events) => events,
+            Err(e) => {
+                self.send_to_ui(UIMessage::Debug(format!(
+                    "Failed to read trace {path}: {e}"
+                )))
+                .await;
+                return;
+            }
+        };
+        if let Some((player, _)) = self.replay.take() {
+            player.abort();
+        }
+        let duration = events.last().map_or(0.0, |e| e.at_ms()) / 1000.0;
+        info!(path, events = events.len(), "replaying trace");
+        self.send_to_ui(UIMessage::Debug(format!(
+            "Replaying {} events ({duration:.1}s) from {path}",
+            events.len()
+        )))
+        .await;
+        let (control_sender, control_receiver) = mpsc::channel(CHANNEL_BUFFER_SIZE);
+        let mut player = ReplayPlayer::new(events, self.io_sender.clone(), control_receiver);
+        let player = tokio::spawn(async move { player.run().await });
+        self.replay = Some((player, control_sender));
+    }
+
+    async fn start_chaos(&mut self, config: ChaosConfig) {
+        if self.nodes.len() < 2 {
+            self.send_to_ui(UIMessage::Debug("Chaos needs at least 2 nodes".to_string()))
+                .await;
+            return;
+        }
+        if let Some(chaos) = self.chaos.take() {
+            chaos.abort();
+        }
+        info!(
+            seed = config.seed,
+            interval_ms = config.interval.as_millis() as u64,
+            "starting chaos"
+        );
+        self.send_to_ui(UIMessage::ChaosStarted(config)).await;
+        let mut runner = ChaosRunner::new(
+            config,
+            self.nodes.clone(),
+            self.io_sender.clone(),
+            self.max_round.clone(),
+        );
+        self.chaos = Some(tokio::spawn(async move { runner.run().await }));
+    }
+
+    #[instrument(name = "chaos", skip(self, action), fields(%action))]
+    async fn apply_chaos(&mut self, step: u64, action: ChaosAction) {
+        if self.chaos.is_none() {
+            // Sent before chaos was stopped
+            return;
+        }
+        info!("injecting fault");
+        // The new cut links replace the old ones in one update, so that the proxy never sees a
+        // healed network in between
+        match &action {
+            ChaosAction::Partition(a, b) => {
+                self.replace_partitions(&cut_links(&[a.clone(), b.clone()]))
+                    .await
+            }
+            ChaosAction::IsolateLeader(leader) => {
+                let others: Vec<u64> = self.nodes.iter().copied().filter(|n| n != leader).collect();
+                self.replace_partitions(&cut_links(&[vec![*leader], others]))
+                    .await
+            }
+            ChaosAction::Bridge { left, right, .. } => {
+                self.replace_partitions(&cut_links(&[left.clone(), right.clone()]))
+                    .await
+            }
+            ChaosAction::Delay(a, b, delay) => {
+                let mut link_delays = self.link_delays.lock().await;
+                link_delays.insert(connection_to_port(a, b), *delay);
+                link_delays.insert(connection_to_port(b, a), *delay);
+            }
+            ChaosAction::Heal => {
+                self.heal().await;
+                self.clear_delays().await;
+            }
         }
+        self.send_network_update().await;
+        self.send_to_ui(UIMessage::ChaosAction(step, action)).await;
+    }
+
+    /// Replaces the cut links with `links`, given in both directions.
+    async fn replace_partitions(&self, links: &[(u64, u64)]) {
+        let cut = links
+            .iter()
+            .map(|(from, to)| connection_to_port(from, to))
+            .collect();
+        *self.partitions.lock().await = cut;
+    }
+
+    /// Restores all links. Link delays and bandwidth limits are kept.
+    async fn heal(&self) {
+        self.partitions.lock().await.clear();
+    }
+
+    async fn clear_delays(&self) {
+        self.link_delays.lock().await.clear();
     }
 
+    #[instrument(name = "scenario", skip(self))]
     async fn handle_scenario(&mut self, scenario_type: String) {
+        info!("running scenario");
         match scenario_type.as_str() {
             "qloss" => {
                 // Remove connections to everyone but next leader
@@ -0,0 +1,64 @@
+/// Parses partition groups like `{1,2} {3,4,5}`.
+pub fn parse_groups(s: &str) -> Option<Vec<Vec<u64>>> {
+    let mut groups = vec![];
+    let mut rest = s.trim();
+    while !rest.is_empty() {
+        let (group, tail) = rest.strip_prefix('{')?.split_once('}')?;
+        let group = group
+            .split(',')
+            .map(|pid| pid.trim().parse().ok())
+            .collect::<Option<Vec<u64>>>()?;
+        groups.push(group);
+        rest = tail.trim_start();
+    }
+    (!groups.is_empty()).then_some(groups)
+}
+
+/// Adds a group with the nodes that are in none. Returns `None` if a node is in more than one
+/// group.
+pub fn complete_groups(mut groups: Vec<Vec<u64>>, nodes: &[u64]) -> Option<Vec<Vec<u64>>> {
+    let mut seen = vec![];
+    for &pid in groups.iter().flatten() {
+        if seen.contains(&pid) {
+            return None;
+        }
+        seen.push(pid);
+    }
+    let rest: Vec<u64> = nodes
+        .iter()
+        .copied()
+        .filter(|pid| !seen.contains(pid))
+        .collect();
+    if !rest.is_empty() {
+        groups.push(rest);
+    }
+    Some(groups)
+}
+
+/// Links between nodes of different groups, in both directions.
+pub fn cut_links(groups: &[Vec<u64>]) -> Vec<(u64, u64)> {
+    let mut links = vec![];
+    for (i, group) in groups.iter().enumerate() {
+        for other in groups.iter().skip(i + 1) {
+            for &a in group {
+                for &b in other {
+                    links.push((a, b));
+                    links.push((b, a));
+                }
+            }
+        }
+    }
+    links
+}
+
+/// Formats groups like `{1,2} {3,4,5}`.
+pub fn format_groups(groups: &[Vec<u64>]) -> String {
+    let groups: Vec<String> = groups
+        .iter()
+        .map(|group| {
+            let pids: Vec<String> = group.iter().map(|pid| pid.to_string()).collect();
+            format!("{{{}}}", pids.join(","))
+        })
+        .collect();
+    groups.join(" ")
+}
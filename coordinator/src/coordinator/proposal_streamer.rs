@@ -1,25 +1,144 @@
-use std::{
-    collections::{HashMap, VecDeque},
-    sync::Arc,
-};
+use rand::seq::SliceRandom;
+use std::fmt;
+use std::time::{Duration, Instant};
+use std::{collections::HashMap, sync::Arc};
 use tokio::io::AsyncWriteExt;
 use tokio::{
     net::tcp::OwnedWriteHalf,
     sync::{mpsc::Sender, Mutex},
 };
+use tracing::{debug, instrument, warn, Span};
 
+use crate::coordinator::freeze::{Freeze, RequestGate};
+use crate::coordinator::in_flight::{
+    FailedProposal, FailureReason, InFlightCommand, SharedInFlight,
+};
+use crate::coordinator::CommandQueue;
 use crate::messages::{
-    coordinator::{KVCommand, Message, Round},
+    coordinator::{Message, Round},
     ui::UIMessage,
     IOMessage,
 };
-use crate::utils::PROPOSE_TICK_RATE;
+use crate::utils::{
+    DEFAULT_PROPOSAL_TIMEOUT, PROPOSE_BURST_WINDOW, PROPOSE_MAX_PER_TICK, PROPOSE_TICK_RATE,
+};
+
+/// Whether the proposer waits for acknowledgements before sending more commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoopMode {
+    /// Commands are sent at the target rate regardless of how many are unacknowledged.
+    Open,
+    /// At most `outstanding` commands are unacknowledged at any time.
+    Closed { outstanding: u64 },
+}
+
+/// Which node commands without an explicit node are proposed at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoutingPolicy {
+    Leader,
+    /// Cycle through the connected nodes.
+    RoundRobin,
+    /// Pick a connected node at random.
+    Random,
+    /// Pick the connected node with the fewest unacknowledged commands.
+    LeastOutstanding,
+}
+
+impl fmt::Display for RoutingPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            RoutingPolicy::Leader => "leader",
+            RoutingPolicy::RoundRobin => "round-robin",
+            RoutingPolicy::Random => "random",
+            RoutingPolicy::LeastOutstanding => "least-outstanding",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProposerConfig {
+    /// Target rate in commands per second, `None` to send as fast as possible.
+    pub rate: Option<u64>,
+    pub mode: LoopMode,
+    /// Maximum number of commands sent with a single socket write.
+    pub write_batch: usize,
+    pub routing: RoutingPolicy,
+    /// How long a command may stay unacknowledged before it is reported as failed.
+    pub timeout: Duration,
+}
+
+impl Default for ProposerConfig {
+    fn default() -> Self {
+        Self {
+            rate: Some(1000),
+            mode: LoopMode::Open,
+            write_batch: 1,
+            routing: RoutingPolicy::Leader,
+            timeout: DEFAULT_PROPOSAL_TIMEOUT,
+        }
+    }
+}
+
+impl fmt::Display for ProposerConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.rate {
+            Some(rate) => write!(f, "{rate} ops/s")?,
+            None => write!(f, "unlimited rate")?,
+        }
+        match self.mode {
+            LoopMode::Open => write!(f, ", open loop")?,
+            LoopMode::Closed { outstanding } => {
+                write!(f, ", closed loop with {outstanding} outstanding")?
+            }
+        }
+        write!(
+            f,
+            ", {} commands per write, routed to {}, timeout {:.1}s",
+            self.write_batch,
+            self.routing,
+            self.timeout.as_secs_f64()
+        )
+    }
+}
+
+/// A single change to the proposer configuration, as entered with the `rate` command.
+#[derive(Debug, Clone, Copy)]
+pub enum ProposerSetting {
+    Rate(Option<u64>),
+    Mode(LoopMode),
+    WriteBatch(usize),
+    Routing(RoutingPolicy),
+    Timeout(Duration),
+}
+
+impl ProposerConfig {
+    pub fn apply(&mut self, setting: ProposerSetting) {
+        match setting {
+            ProposerSetting::Rate(rate) => self.rate = rate,
+            ProposerSetting::Mode(mode) => self.mode = mode,
+            ProposerSetting::WriteBatch(size) => self.write_batch = size.max(1),
+            ProposerSetting::Routing(routing) => self.routing = routing,
+            ProposerSetting::Timeout(timeout) => self.timeout = timeout,
+        }
+    }
+}
 
 pub struct ProposalStreamer {
     io_sender: Sender<IOMessage>,
     op_sockets: Arc<Mutex<HashMap<u64, OwnedWriteHalf>>>,
-    cmd_queue: Arc<Mutex<VecDeque<(KVCommand, Option<u64>)>>>,
+    cmd_queue: CommandQueue,
     max_round: Arc<Mutex<Option<Round>>>,
+    in_flight: SharedInFlight,
+    config: Arc<Mutex<ProposerConfig>>,
+    gate: RequestGate,
+    /// Number of commands that may be sent according to the target rate.
+    credit: f64,
+    last_tick: Instant,
+    /// Index of the next node for round-robin routing.
+    next_node: usize,
+    /// Whether proposals are held because there is no node to send them to.
+    holding: bool,
     last_queue_size: usize,
     current_batch_size: usize,
     currently_batching: bool,
@@ -29,28 +148,108 @@ impl ProposalStreamer {
     pub fn new(
         io_sender: Sender<IOMessage>,
         op_sockets: Arc<Mutex<HashMap<u64, OwnedWriteHalf>>>,
-        cmd_queue: Arc<Mutex<VecDeque<(KVCommand, Option<u64>)>>>,
+        cmd_queue: CommandQueue,
         max_round: Arc<Mutex<Option<Round>>>,
+        in_flight: SharedInFlight,
+        config: Arc<Mutex<ProposerConfig>>,
+        gate: RequestGate,
     ) -> Self {
         Self {
             io_sender,
             op_sockets,
             cmd_queue,
             max_round,
+            in_flight,
+            config,
+            gate,
+            credit: 0.0,
+            last_tick: Instant::now(),
+            next_node: 0,
+            holding: false,
             last_queue_size: 0,
             current_batch_size: 0,
             currently_batching: false,
         }
     }
 
-    pub async fn propose_command(&self, cmd: KVCommand, pid: Option<u64>) {
-        let proposer = pid.unwrap_or((*self.max_round.lock().await).unwrap().leader);
-        if let Some(writer) = self.op_sockets.lock().await.get_mut(&proposer) {
-            let request = Message::APIRequest(cmd);
-            let mut data = serde_json::to_vec(&request).expect("could not serialize cmd");
+    /// Picks the node to propose at according to the routing policy, out of the connected ones.
+    /// Returns `None` if there is no suitable node, e.g. if no leader is known.
+    async fn route(&mut self, routing: RoutingPolicy, max_round: Option<Round>) -> Option<u64> {
+        let mut nodes: Vec<u64> = self.op_sockets.lock().await.keys().copied().collect();
+        if nodes.is_empty() {
+            return None;
+        }
+        nodes.sort();
+        match routing {
+            RoutingPolicy::Leader => max_round
+                .map(|round| round.leader)
+                .filter(|leader| nodes.contains(leader)),
+            RoutingPolicy::RoundRobin => {
+                let pid = nodes[self.next_node % nodes.len()];
+                self.next_node = self.next_node.wrapping_add(1);
+                Some(pid)
+            }
+            RoutingPolicy::Random => nodes.choose(&mut rand::thread_rng()).copied(),
+            RoutingPolicy::LeastOutstanding => {
+                let in_flight = self.in_flight.lock().await;
+                nodes
+                    .iter()
+                    .min_by_key(|pid| in_flight.outstanding_at(**pid))
+                    .copied()
+            }
+        }
+    }
+
+    /// Sends `commands` to `proposer` with a single socket write and tracks them until they are
+    /// acknowledged. Returns the commands if `proposer` is not connected.
+    #[instrument(name = "proposal", skip_all, fields(pid = proposer, ballot, size = commands.len()))]
+    async fn propose_commands(
+        &self,
+        commands: Vec<InFlightCommand>,
+        proposer: u64,
+    ) -> Result<(), Vec<InFlightCommand>> {
+        if let Some(round) = *self.max_round.lock().await {
+            Span::current().record("ballot", round.round_num);
+        }
+        let mut op_sockets = self.op_sockets.lock().await;
+        let Some(writer) = op_sockets.get_mut(&proposer) else {
+            return Err(commands);
+        };
+        let mut data = vec![];
+        let mut in_flight = self.in_flight.lock().await;
+        for mut command in commands {
+            debug!(cmd = ?command.cmd, attempt = command.attempts + 1, "proposing");
+            let cmd = command.cmd.clone();
+            command.proposer = proposer;
+            command.attempts += 1;
+            let request_id = in_flight.sent(command);
+            let request = Message::APIRequest(cmd, Some(request_id));
+            serde_json::to_writer(&mut data, &request).expect("could not serialize cmd");
             data.push(b'\n');
-            writer.write_all(&data).await.unwrap();
-        } else {
+        }
+        drop(in_flight);
+        match self.gate.freeze(proposer) {
+            // The node can't resume before the requests are held, since resuming it locks the
+            // sockets
+            Some(Freeze::Paused) => self.gate.hold(proposer, data).await,
+            Some(Freeze::ClientIsolated) => debug!("client link cut, dropping proposals"),
+            None => {
+                if let Err(e) = writer.write_all(&data).await {
+                    // The node crashed. Its commands are retried after the next leader change
+                    // or time out.
+                    warn!(error = %e, "failed to send proposals");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Notifies the UI once when proposals have to be held because there is no node to send
+    /// them to.
+    async fn hold(&mut self) {
+        if !self.holding {
+            self.holding = true;
+            warn!("no node to propose at, holding proposals");
             self.io_sender
                 .send(IOMessage::UIMessage(UIMessage::ClusterUnreachable))
                 .await
@@ -58,6 +257,15 @@ impl ProposalStreamer {
         }
     }
 
+    async fn report_failures(&self, failures: Vec<FailedProposal>) {
+        if !failures.is_empty() {
+            self.io_sender
+                .send(IOMessage::UIMessage(UIMessage::ProposalsFailed(failures)))
+                .await
+                .unwrap();
+        }
+    }
+
     async fn send_new_batch_size(&self) {
         self.io_sender
             .send(IOMessage::UIMessage(UIMessage::ProposalStatus(
@@ -67,12 +275,37 @@ impl ProposalStreamer {
             .unwrap();
     }
 
+    /// Number of commands that may be sent in this tick according to the configured rate and
+    /// loop mode.
+    async fn allowance(&mut self, config: &ProposerConfig) -> usize {
+        let now = Instant::now();
+        let elapsed = now - self.last_tick;
+        self.last_tick = now;
+        let mut allowance = match config.rate {
+            Some(rate) => {
+                // Cap the credit so that an idle period does not turn into a burst
+                let max_credit = (rate as f64 * PROPOSE_BURST_WINDOW.as_secs_f64()).max(1.0);
+                self.credit = (self.credit + rate as f64 * elapsed.as_secs_f64()).min(max_credit);
+                self.credit as usize
+            }
+            None => PROPOSE_MAX_PER_TICK,
+        };
+        if let LoopMode::Closed { outstanding } = config.mode {
+            let in_flight = self.in_flight.lock().await.outstanding();
+            allowance = allowance.min(outstanding.saturating_sub(in_flight) as usize);
+        }
+        allowance
+    }
+
     pub async fn run(&mut self) {
         let mut propose_interval = tokio::time::interval(PROPOSE_TICK_RATE);
         loop {
             tokio::select! {
                 _ = propose_interval.tick() => {
-                    let mut queue = self.cmd_queue.lock().await;
+                    let config = *self.config.lock().await;
+                    let allowance = self.allowance(&config).await;
+                    let cmd_queue = self.cmd_queue.clone();
+                    let mut queue = cmd_queue.lock().await;
                     let mut queue_len = queue.len();
                     if queue_len > self.last_queue_size {
                         // Must have batched new proposals
@@ -85,9 +318,80 @@ impl ProposalStreamer {
                         self.current_batch_size = 0; // Signal to UI that batching is "finished"
                         self.send_new_batch_size().await;
                     }
-                    if let Some((cmd, pid)) = queue.pop_back() {
-                        self.propose_command(cmd, pid).await;
-                        queue_len -= 1;
+                    let max_round = *self.max_round.lock().await;
+                    let mut sent = 0;
+                    let mut sent_from_queue = 0;
+                    let mut failures = vec![];
+                    while sent < allowance {
+                        let batch_limit = config.write_batch.min(allowance - sent);
+                        // Retries of routed commands go before new commands
+                        let has_retries = self.in_flight.lock().await.has_retries();
+                        let pid = match (has_retries, queue.back()) {
+                            (true, _) => None,
+                            (false, Some((_, pid, _))) => *pid,
+                            (false, None) => break,
+                        };
+                        let proposer = match pid {
+                            Some(pid) => pid,
+                            None => match self.route(config.routing, max_round).await {
+                                Some(proposer) => proposer,
+                                None => {
+                                    self.hold().await;
+                                    break;
+                                }
+                            },
+                        };
+                        // Group consecutive commands to the same node into one write
+                        let mut batch = vec![];
+                        if has_retries {
+                            let mut in_flight = self.in_flight.lock().await;
+                            while batch.len() < batch_limit {
+                                match in_flight.next_retry() {
+                                    Some(command) => batch.push(command),
+                                    None => break,
+                                }
+                            }
+                        } else {
+                            while batch.len() < batch_limit {
+                                match queue.back() {
+                                    Some((_, next_pid, _)) if *next_pid == pid => {
+                                        let (cmd, pid, source) = queue.pop_back().unwrap();
+                                        batch.push(InFlightCommand {
+                                            cmd,
+                                            pid,
+                                            source,
+                                            proposer,
+                                            first_sent: Instant::now(),
+                                            attempts: 0,
+                                            op_id: None,
+                                        });
+                                    }
+                                    _ => break,
+                                }
+                            }
+                            sent_from_queue += batch.len();
+                        }
+                        sent += batch.len();
+                        if let Err(batch) = self.propose_commands(batch, proposer).await {
+                            match pid {
+                                Some(pid) => failures.extend(batch.into_iter().map(|c| {
+                                    FailedProposal::new(c, FailureReason::NodeUnreachable(pid))
+                                })),
+                                None => {
+                                    // The node disconnected after it was picked
+                                    let mut in_flight = self.in_flight.lock().await;
+                                    batch.into_iter().for_each(|c| in_flight.retry(c));
+                                }
+                            }
+                        } else {
+                            self.holding = false;
+                        }
+                    }
+                    drop(queue);
+                    self.report_failures(failures).await;
+                    queue_len -= sent_from_queue;
+                    if config.rate.is_some() {
+                        self.credit -= sent as f64;
                     }
                     self.last_queue_size = queue_len;
                 },
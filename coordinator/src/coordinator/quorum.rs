@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+
+/// A group of nodes that can reach each other, directly or through other nodes of the group.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Component {
+    pub nodes: Vec<u64>,
+    /// Whether a node of the group has direct links to a read quorum. A leader only talks to
+    /// its followers directly, so nodes reachable through others don't count.
+    pub read_quorum: bool,
+    /// Whether a node of the group has direct links to a read and a write quorum.
+    pub write_quorum: bool,
+}
+
+/// What the current links allow the cluster to do.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QuorumAnalysis {
+    pub read_quorum_size: usize,
+    pub write_quorum_size: usize,
+    /// Components of the nodes that are up, largest first.
+    pub components: Vec<Component>,
+    /// Nodes with direct links to a read quorum, which can be elected leader.
+    pub candidates: Vec<u64>,
+    /// Candidates that also have direct links to a write quorum, so they can decide entries as
+    /// leader.
+    pub deciders: Vec<u64>,
+}
+
+impl QuorumAnalysis {
+    /// Analyzes the links between the nodes that are `up`, where `cut` holds the disconnected
+    /// pairs. `flex_quorum` holds the read and write quorum sizes, which are majorities if unset.
+    pub fn analyze(
+        nodes: &[u64],
+        up: &[u64],
+        cut: &HashSet<(u64, u64)>,
+        flex_quorum: Option<(usize, usize)>,
+    ) -> Self {
+        let majority = nodes.len() / 2 + 1;
+        let (read_quorum_size, write_quorum_size) = flex_quorum.unwrap_or((majority, majority));
+        let mut up = up.to_vec();
+        up.sort();
+        let connected =
+            |a: u64, b: u64| a == b || !(cut.contains(&(a, b)) || cut.contains(&(b, a)));
+
+        let reach = |pid: u64| up.iter().filter(|&&other| connected(pid, other)).count();
+        let candidates: Vec<u64> = up
+            .iter()
+            .copied()
+            .filter(|&pid| reach(pid) >= read_quorum_size)
+            .collect();
+        let deciders: Vec<u64> = candidates
+            .iter()
+            .copied()
+            .filter(|&pid| reach(pid) >= write_quorum_size)
+            .collect();
+
+        let mut components: Vec<Component> = vec![];
+        let mut visited: HashSet<u64> = HashSet::new();
+        for &start in &up {
+            if !visited.insert(start) {
+                continue;
+            }
+            let mut component = vec![start];
+            let mut next = 0;
+            while let Some(&pid) = component.get(next) {
+                for &other in &up {
+                    if connected(pid, other) && visited.insert(other) {
+                        component.push(other);
+                    }
+                }
+                next += 1;
+            }
+            component.sort();
+            components.push(Component {
+                read_quorum: component.iter().any(|pid| candidates.contains(pid)),
+                write_quorum: component.iter().any(|pid| deciders.contains(pid)),
+                nodes: component,
+            });
+        }
+        components.sort_by_key(|c| std::cmp::Reverse(c.nodes.len()));
+        Self {
+            read_quorum_size,
+            write_quorum_size,
+            components,
+            candidates,
+            deciders,
+        }
+    }
+
+    /// Whether some leader can decide entries with the current links.
+    pub fn can_decide(&self) -> bool {
+        !self.deciders.is_empty()
+    }
+}
+
+impl fmt::Display for QuorumAnalysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pids = |pids: &[u64]| {
+            let pids: Vec<String> = pids.iter().map(|pid| pid.to_string()).collect();
+            pids.join(",")
+        };
+        write!(
+            f,
+            "Quorums: read {}, write {} | Components:",
+            self.read_quorum_size, self.write_quorum_size
+        )?;
+        for component in &self.components {
+            let quorums = match (component.read_quorum, component.write_quorum) {
+                (_, true) => "read+write",
+                (true, false) => "read",
+                (false, false) => "no quorum",
+            };
+            write!(f, " {{{}}} {quorums}", pids(&component.nodes))?;
+        }
+        match self.candidates.is_empty() {
+            true => write!(f, " | No leader candidates")?,
+            false => write!(f, " | Leader candidates: {}", pids(&self.candidates))?,
+        }
+        match self.can_decide() {
+            true => write!(f, " | Can decide through: {}", pids(&self.deciders)),
+            false => write!(f, " | Cannot decide"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cuts every link between `nodes` except `links`.
+    fn cut_all_but(nodes: &[u64], links: &[(u64, u64)]) -> HashSet<(u64, u64)> {
+        let linked = |a, b| links.contains(&(a, b)) || links.contains(&(b, a));
+        let mut cut = HashSet::new();
+        for &a in nodes {
+            for &b in nodes {
+                if a < b && !linked(a, b) {
+                    cut.insert((a, b));
+                }
+            }
+        }
+        cut
+    }
+
+    #[test]
+    fn fully_connected_cluster_can_decide_anywhere() {
+        let nodes = [1, 2, 3, 4, 5];
+        let analysis = QuorumAnalysis::analyze(&nodes, &nodes, &HashSet::new(), None);
+        assert_eq!(analysis.components.len(), 1);
+        assert!(analysis.components[0].write_quorum);
+        assert_eq!(analysis.candidates, nodes);
+        assert_eq!(analysis.deciders, nodes);
+    }
+
+    #[test]
+    fn only_the_majority_side_of_a_partition_has_a_quorum() {
+        let nodes = [1, 2, 3, 4, 5];
+        let cut = cut_all_but(&nodes, &[(1, 2), (3, 4), (3, 5), (4, 5)]);
+        let analysis = QuorumAnalysis::analyze(&nodes, &nodes, &cut, None);
+        assert_eq!(analysis.components[0].nodes, vec![3, 4, 5]);
+        assert!(analysis.components[0].write_quorum);
+        assert_eq!(analysis.components[1].nodes, vec![1, 2]);
+        assert!(!analysis.components[1].read_quorum);
+        assert_eq!(analysis.deciders, vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn chain_has_no_quorum_without_direct_links() {
+        // Every node of the chain reaches all others through its neighbours, but has direct
+        // links to at most 3 of the 7 nodes, fewer than a majority of 4
+        let nodes = [1, 2, 3, 4, 5, 6, 7];
+        let chain: Vec<(u64, u64)> = nodes.windows(2).map(|w| (w[0], w[1])).collect();
+        let cut = cut_all_but(&nodes, &chain);
+        let analysis = QuorumAnalysis::analyze(&nodes, &nodes, &cut, None);
+        assert_eq!(analysis.components.len(), 1);
+        assert_eq!(analysis.components[0].nodes, nodes);
+        assert!(!analysis.components[0].read_quorum);
+        assert!(analysis.candidates.is_empty());
+        assert!(!analysis.can_decide());
+    }
+
+    #[test]
+    fn leader_needs_direct_links_to_a_quorum() {
+        // 1-2-3 with 4 and 5 crashed: only 2 is linked to a majority of 3
+        let nodes = [1, 2, 3, 4, 5];
+        let cut = cut_all_but(&nodes, &[(1, 2), (2, 3)]);
+        let analysis = QuorumAnalysis::analyze(&nodes, &[1, 2, 3], &cut, None);
+        assert!(analysis.components[0].write_quorum);
+        assert_eq!(analysis.candidates, vec![2]);
+        assert_eq!(analysis.deciders, vec![2]);
+    }
+
+    #[test]
+    fn flexible_quorums_separate_electing_from_deciding() {
+        let nodes = [1, 2, 3, 4, 5];
+        let cut = cut_all_but(&nodes, &[(1, 2), (3, 4), (3, 5), (4, 5)]);
+        let analysis = QuorumAnalysis::analyze(&nodes, &nodes, &cut, Some((2, 4)));
+        assert!(analysis.components.iter().all(|c| c.read_quorum));
+        assert!(analysis.components.iter().all(|c| !c.write_quorum));
+        assert_eq!(analysis.candidates, nodes);
+        assert!(!analysis.can_decide());
+    }
+}
@@ -0,0 +1,249 @@
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{Receiver, Sender};
+
+use crate::coordinator::traffic::{TrafficEvent, Verdict};
+use crate::coordinator::NetworkState;
+use crate::messages::coordinator::APIResponse;
+use crate::messages::ui::UIMessage;
+use crate::messages::IOMessage;
+use crate::utils::{REPLAY_TICK_RATE, REPLAY_TRAFFIC_WINDOW};
+
+#[derive(Debug, Clone, Copy)]
+pub enum ReplayControl {
+    Play,
+    Pause,
+    Seek(ReplaySeek),
+    Speed(f64),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ReplaySeek {
+    To(Duration),
+    Forward(Duration),
+    Back(Duration),
+}
+
+/// Messages sent over a link during the last `REPLAY_TRAFFIC_WINDOW` of the replay.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LinkTraffic {
+    pub forwarded: u64,
+    pub dropped: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplayStatus {
+    pub position: Duration,
+    pub duration: Duration,
+    pub paused: bool,
+    pub speed: f64,
+    /// Traffic per directed link `(from, to)`.
+    pub traffic: HashMap<(u64, u64), LinkTraffic>,
+}
+
+/// Plays a recorded traffic trace back to the UI, as if the cluster was running.
+pub struct ReplayPlayer {
+    events: Vec<TrafficEvent>,
+    io_sender: Sender<IOMessage>,
+    controls: Receiver<ReplayControl>,
+    position_ms: f64,
+    duration_ms: f64,
+    paused: bool,
+    speed: f64,
+    /// Index of the first event after the current position.
+    next_event: usize,
+    network_state: Option<NetworkState>,
+}
+
+impl ReplayPlayer {
+    pub fn new(
+        events: Vec<TrafficEvent>,
+        io_sender: Sender<IOMessage>,
+        controls: Receiver<ReplayControl>,
+    ) -> Self {
+        let duration_ms = events.last().map_or(0.0, |e| e.at_ms());
+        Self {
+            events,
+            io_sender,
+            controls,
+            position_ms: 0.0,
+            duration_ms,
+            paused: false,
+            speed: 1.0,
+            next_event: 0,
+            network_state: None,
+        }
+    }
+
+    pub async fn run(&mut self) {
+        self.seek(0.0).await;
+        let mut interval = tokio::time::interval(REPLAY_TICK_RATE);
+        let mut last_tick = Instant::now();
+        loop {
+            tokio::select! {
+                Some(control) = self.controls.recv() => {
+                    self.control(control).await;
+                    self.send_status().await;
+                }
+                _ = interval.tick() => {
+                    let elapsed_ms = last_tick.elapsed().as_secs_f64() * 1000.0;
+                    last_tick = Instant::now();
+                    if !self.paused {
+                        let target = (self.position_ms + elapsed_ms * self.speed).min(self.duration_ms);
+                        self.play_until(target).await;
+                        if target >= self.duration_ms {
+                            self.paused = true;
+                            self.log("Replay finished".to_string()).await;
+                        }
+                    }
+                    self.send_status().await;
+                }
+            }
+        }
+    }
+
+    async fn control(&mut self, control: ReplayControl) {
+        match control {
+            ReplayControl::Play => {
+                if self.position_ms >= self.duration_ms {
+                    self.seek(0.0).await;
+                }
+                self.paused = false;
+            }
+            ReplayControl::Pause => self.paused = true,
+            ReplayControl::Seek(seek) => {
+                let target = match seek {
+                    ReplaySeek::To(at) => at.as_secs_f64() * 1000.0,
+                    ReplaySeek::Forward(by) => self.position_ms + by.as_secs_f64() * 1000.0,
+                    ReplaySeek::Back(by) => self.position_ms - by.as_secs_f64() * 1000.0,
+                };
+                self.seek(target.clamp(0.0, self.duration_ms)).await;
+            }
+            ReplayControl::Speed(speed) => self.speed = speed,
+        }
+    }
+
+    /// Sends the events up to `target_ms` to the UI.
+    async fn play_until(&mut self, target_ms: f64) {
+        while let Some(event) = self.events.get(self.next_event) {
+            if event.at_ms() > target_ms {
+                break;
+            }
+            match event.clone() {
+                TrafficEvent::Network { at_ms, state } => {
+                    self.log_network_changes(at_ms, &state).await;
+                    self.network_state = Some(*state.clone());
+                    self.send(UIMessage::OmnipaxosNetworkUpdate(*state)).await;
+                }
+                TrafficEvent::Decided { pid, idx, .. } => {
+                    self.send(UIMessage::OmnipaxosResponse(APIResponse::Decided(idx), pid))
+                        .await;
+                }
+                TrafficEvent::Message { .. } => (),
+            }
+            self.next_event += 1;
+        }
+        self.position_ms = target_ms;
+    }
+
+    /// Jumps to `target_ms` and shows the latest network state and decided index before it.
+    async fn seek(&mut self, target_ms: f64) {
+        self.position_ms = target_ms;
+        self.next_event = self.events.partition_point(|e| e.at_ms() <= target_ms);
+        let past = &self.events[..self.next_event];
+        let network_state = past.iter().rev().find_map(|e| match e {
+            TrafficEvent::Network { state, .. } => Some(*state.clone()),
+            _ => None,
+        });
+        let decided = past.iter().rev().find_map(|e| match e {
+            TrafficEvent::Decided { pid, idx, .. } => Some((*pid, *idx)),
+            _ => None,
+        });
+        if let Some(state) = &network_state {
+            self.send(UIMessage::OmnipaxosNetworkUpdate(state.clone()))
+                .await;
+        }
+        self.network_state = network_state;
+        let (pid, idx) = decided.unwrap_or_default();
+        self.send(UIMessage::OmnipaxosResponse(APIResponse::Decided(idx), pid))
+            .await;
+    }
+
+    async fn log_network_changes(&self, at_ms: f64, state: &NetworkState) {
+        let at = format!("[{:.3}s]", at_ms / 1000.0);
+        let old_round = self.network_state.as_ref().and_then(|s| s.max_round);
+        if let Some(round) = state.max_round.filter(|r| Some(*r) != old_round) {
+            self.log(format!(
+                "{at} Node {} became leader in round {}",
+                round.leader, round.round_num
+            ))
+            .await;
+        }
+        let old_partitions = self
+            .network_state
+            .as_ref()
+            .map(|s| s.partitions.clone())
+            .unwrap_or_default();
+        if state.partitions != old_partitions {
+            let mut cut: Vec<&(u64, u64)> = state.partitions.iter().collect();
+            cut.sort();
+            let cut: Vec<String> = cut.iter().map(|(a, b)| format!("{a}-{b}")).collect();
+            match cut.is_empty() {
+                true => self.log(format!("{at} All links restored")).await,
+                false => self.log(format!("{at} Cut links: {}", cut.join(" "))).await,
+            }
+        }
+        let old_alive: HashSet<u64> = self
+            .network_state
+            .as_ref()
+            .map(|s| s.alive_nodes.iter().copied().collect())
+            .unwrap_or_default();
+        for pid in state.alive_nodes.iter().filter(|p| !old_alive.contains(p)) {
+            self.log(format!("{at} Node {pid} joined")).await;
+        }
+        for pid in old_alive.iter().filter(|p| !state.alive_nodes.contains(p)) {
+            self.log(format!("{at} Node {pid} crashed")).await;
+        }
+    }
+
+    fn traffic(&self) -> HashMap<(u64, u64), LinkTraffic> {
+        let window_start = self.position_ms - REPLAY_TRAFFIC_WINDOW.as_secs_f64() * 1000.0;
+        let first = self.events[..self.next_event].partition_point(|e| e.at_ms() <= window_start);
+        let mut traffic: HashMap<(u64, u64), LinkTraffic> = HashMap::new();
+        for event in &self.events[first..self.next_event] {
+            if let TrafficEvent::Message {
+                from, to, verdict, ..
+            } = event
+            {
+                let link = traffic.entry((*from, *to)).or_default();
+                match verdict {
+                    Verdict::Forwarded => link.forwarded += 1,
+                    Verdict::Dropped => link.dropped += 1,
+                }
+            }
+        }
+        traffic
+    }
+
+    async fn send_status(&self) {
+        let status = ReplayStatus {
+            position: Duration::from_secs_f64(self.position_ms / 1000.0),
+            duration: Duration::from_secs_f64(self.duration_ms / 1000.0),
+            paused: self.paused,
+            speed: self.speed,
+            traffic: self.traffic(),
+        };
+        self.send(UIMessage::ReplayStatus(status)).await;
+    }
+
+    async fn log(&self, log: String) {
+        self.send(UIMessage::Debug(log)).await;
+    }
+
+    async fn send(&self, msg: UIMessage) {
+        self.io_sender
+            .send(IOMessage::UIMessage(msg))
+            .await
+            .unwrap();
+    }
+}
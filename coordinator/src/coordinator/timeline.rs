@@ -0,0 +1,93 @@
+use chrono::{DateTime, Local};
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use crate::messages::coordinator::Round;
+
+/// A round change observed by the coordinator.
+#[derive(Debug, Clone)]
+pub struct ElectionRecord {
+    pub time: DateTime<Local>,
+    pub round: Round,
+    pub old_leader: Option<u64>,
+    /// Links that were cut when the round changed.
+    pub partitions: Vec<(u64, u64)>,
+    /// Time from when the cluster stopped deciding pending writes, or from the first write sent
+    /// after the round change if none were pending, until the first decision after it. `None`
+    /// until the new leader decides something.
+    pub unavailability: Option<Duration>,
+    pub(crate) changed_at: Instant,
+    /// `None` if no write was pending at the round change.
+    unavailable_since: Option<Instant>,
+}
+
+/// History of leader elections, used to measure the unavailability window of each failover.
+#[derive(Debug, Default)]
+pub struct ElectionTimeline {
+    records: Vec<ElectionRecord>,
+    decided_idx: u64,
+    last_decided_at: Option<Instant>,
+}
+
+impl ElectionTimeline {
+    pub fn records(&self) -> &[ElectionRecord] {
+        &self.records
+    }
+
+    pub fn decided_idx(&self) -> u64 {
+        self.decided_idx
+    }
+
+    /// Records a round change. `oldest_pending` is when the oldest write that is still not
+    /// decided was sent.
+    pub fn record_round_change(
+        &mut self,
+        old_round: Option<Round>,
+        round: Round,
+        partitions: &HashSet<(u64, u64)>,
+        oldest_pending: Option<Instant>,
+    ) {
+        let mut partitions: Vec<(u64, u64)> = partitions.iter().copied().collect();
+        partitions.sort();
+        let changed_at = Instant::now();
+        // Idle time is not unavailability, so the window starts at the first write that went
+        // undecided, but not before the last decision. Without one, it starts at the first write
+        // sent after the change.
+        let unavailable_since = oldest_pending.map(|sent| match self.last_decided_at {
+            Some(decided) => sent.max(decided),
+            None => sent,
+        });
+        self.records.push(ElectionRecord {
+            time: Local::now(),
+            round,
+            old_leader: old_round.map(|r| r.leader),
+            partitions,
+            unavailability: None,
+            changed_at,
+            unavailable_since,
+        });
+    }
+
+    /// Updates the decided index. `first_write_sent` is when the first write after the last round
+    /// change was sent. Returns true if this closed the unavailability window of an election.
+    pub fn handle_decided(&mut self, decided_idx: u64, first_write_sent: Option<Instant>) -> bool {
+        if decided_idx <= self.decided_idx {
+            return false;
+        }
+        self.decided_idx = decided_idx;
+        let now = Instant::now();
+        self.last_decided_at = Some(now);
+        let mut closed_window = false;
+        for record in self
+            .records
+            .iter_mut()
+            .rev()
+            .take_while(|r| r.unavailability.is_none())
+        {
+            let since = record.unavailable_since.or(first_write_sent).unwrap_or(now);
+            record.unavailability = Some(now.saturating_duration_since(since));
+            closed_window = true;
+        }
+        closed_window
+    }
+}
@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::time::Instant;
+use tokio::sync::mpsc::{self, UnboundedSender};
+use tracing::warn;
+
+use crate::coordinator::inspector::MessageKind;
+use crate::coordinator::NetworkState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verdict {
+    Forwarded,
+    Dropped,
+}
+
+/// An entry of a traffic trace. Times are in milliseconds since the recording started.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum TrafficEvent {
+    /// A message between two nodes that went through the proxy.
+    Message {
+        at_ms: f64,
+        from: u64,
+        to: u64,
+        bytes: usize,
+        verdict: Verdict,
+        #[serde(default)]
+        delay_ms: u64,
+        /// Type of the message, if the proxy inspected it.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        kind: Option<MessageKind>,
+    },
+    /// The network state shown by the UI changed.
+    Network {
+        at_ms: f64,
+        state: Box<NetworkState>,
+    },
+    /// The decided index of the cluster increased.
+    Decided { at_ms: f64, pid: u64, idx: u64 },
+}
+
+impl TrafficEvent {
+    pub fn at_ms(&self) -> f64 {
+        match self {
+            TrafficEvent::Message { at_ms, .. }
+            | TrafficEvent::Network { at_ms, .. }
+            | TrafficEvent::Decided { at_ms, .. } => *at_ms,
+        }
+    }
+}
+
+/// Writes the traffic of the cluster to a file as JSON lines, from a background task so the
+/// proxy is never blocked on the disk.
+#[derive(Clone)]
+pub struct TrafficRecorder {
+    start: Instant,
+    sender: UnboundedSender<TrafficEvent>,
+    decided_idx: u64,
+}
+
+impl TrafficRecorder {
+    pub fn new(path: &str) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        let (sender, mut receiver) = mpsc::unbounded_channel::<TrafficEvent>();
+        let path = path.to_string();
+        tokio::spawn(async move {
+            while let Some(event) = receiver.recv().await {
+                let mut result = write_event(&mut writer, &event);
+                while let Ok(event) = receiver.try_recv() {
+                    result = result.and_then(|_| write_event(&mut writer, &event));
+                }
+                if let Err(e) = result.and_then(|_| writer.flush()) {
+                    warn!(path, error = %e, "failed to write traffic trace");
+                    break;
+                }
+            }
+        });
+        Ok(Self {
+            start: Instant::now(),
+            sender,
+            decided_idx: 0,
+        })
+    }
+
+    fn elapsed_ms(&self) -> f64 {
+        self.start.elapsed().as_secs_f64() * 1000.0
+    }
+
+    pub fn message(
+        &self,
+        from: u64,
+        to: u64,
+        bytes: usize,
+        verdict: Verdict,
+        delay_ms: u64,
+        kind: Option<MessageKind>,
+    ) {
+        _ = self.sender.send(TrafficEvent::Message {
+            at_ms: self.elapsed_ms(),
+            from,
+            to,
+            bytes,
+            verdict,
+            delay_ms,
+            kind,
+        });
+    }
+
+    pub fn network(&self, state: &NetworkState) {
+        _ = self.sender.send(TrafficEvent::Network {
+            at_ms: self.elapsed_ms(),
+            state: Box::new(state.clone()),
+        });
+    }
+
+    /// Records the decided index reported by `pid` if no node reported it before.
+    pub fn decided(&mut self, pid: u64, idx: u64) {
+        if idx > self.decided_idx {
+            self.decided_idx = idx;
+            _ = self.sender.send(TrafficEvent::Decided {
+                at_ms: self.elapsed_ms(),
+                pid,
+                idx,
+            });
+        }
+    }
+}
+
+fn write_event(writer: &mut BufWriter<File>, event: &TrafficEvent) -> io::Result<()> {
+    serde_json::to_writer(&mut *writer, event)?;
+    writer.write_all(b"\n")
+}
+
+/// Reads a trace written by `TrafficRecorder`, ordered by time.
+pub fn read_trace(path: &str) -> io::Result<Vec<TrafficEvent>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut events = vec![];
+    for (line_num, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event = serde_json::from_str(&line).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {}: {e}", line_num + 1),
+            )
+        })?;
+        events.push(event);
+    }
+    events.sort_by(|a: &TrafficEvent, b| a.at_ms().total_cmp(&b.at_ms()));
+    Ok(events)
+}
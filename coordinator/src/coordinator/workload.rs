@@ -0,0 +1,409 @@
+use rand::distributions::{Alphanumeric, DistString};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::Sender;
+
+use crate::coordinator::{CommandQueue, CommandSource, KeyValue};
+use crate::messages::coordinator::KVCommand;
+use crate::messages::ui::UIMessage;
+use crate::messages::IOMessage;
+use crate::utils::{parse_duration, PROPOSE_TICK_RATE, WORKLOAD_QUEUE_TARGET};
+
+const WORKLOAD_KEY_PREFIX: &str = "key";
+const DEFAULT_ZIPFIAN_THETA: f64 = 0.99;
+const MAX_KEY_SPACE: u64 = 1_000_000_000;
+/// Terms of the zeta function that are summed exactly, the rest is approximated by an integral.
+const ZETA_EXACT_TERMS: u64 = 10_000;
+
+/// How keys are picked from the key space.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum KeyDistribution {
+    Uniform,
+    Zipfian {
+        theta: f64,
+    },
+    /// `hot_op_fraction` of the operations go to the first `hot_fraction` of the key space.
+    Hotspot {
+        hot_fraction: f64,
+        hot_op_fraction: f64,
+    },
+    /// Zipfian over the most recently inserted keys.
+    Latest,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ValueSize {
+    Fixed { size: usize },
+    Uniform { min: usize, max: usize },
+}
+
+/// A workload modeled on the YCSB core workloads. Ratios are relative to each other and don't
+/// need to sum up to 1.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkloadSpec {
+    pub read_ratio: f64,
+    pub write_ratio: f64,
+    pub delete_ratio: f64,
+    pub key_space: u64,
+    pub key_distribution: KeyDistribution,
+    pub value_size: ValueSize,
+    /// Writes insert new keys instead of updating existing ones (YCSB D and E).
+    #[serde(default)]
+    pub insert_new_keys: bool,
+    /// Writes are preceded by a read of the same key (YCSB F).
+    #[serde(default)]
+    pub read_modify_write: bool,
+    /// Stop after this many operations.
+    pub ops: Option<u64>,
+    /// Stop after this many seconds.
+    pub duration_secs: Option<f64>,
+    /// Seed for the key and value generator. Picked randomly if not set.
+    pub seed: Option<u64>,
+}
+
+impl Default for WorkloadSpec {
+    fn default() -> Self {
+        Self {
+            read_ratio: 0.5,
+            write_ratio: 0.5,
+            delete_ratio: 0.0,
+            key_space: 1000,
+            key_distribution: KeyDistribution::Uniform,
+            value_size: ValueSize::Fixed { size: 16 },
+            insert_new_keys: false,
+            read_modify_write: false,
+            ops: Some(10000),
+            duration_secs: None,
+            seed: None,
+        }
+    }
+}
+
+impl WorkloadSpec {
+    /// Returns the YCSB core workload with the given name (a-f) or the default "custom" workload.
+    /// Scans (workload E) are not supported by the KV store and are issued as reads instead.
+    pub fn preset(name: &str) -> Option<Self> {
+        let zipfian = KeyDistribution::Zipfian {
+            theta: DEFAULT_ZIPFIAN_THETA,
+        };
+        let (read_ratio, write_ratio, key_distribution) = match name {
+            "custom" => return Some(Self::default()),
+            "a" => (0.5, 0.5, zipfian),
+            "b" => (0.95, 0.05, zipfian),
+            "c" => (1.0, 0.0, zipfian),
+            "d" => (0.95, 0.05, KeyDistribution::Latest),
+            "e" => (0.95, 0.05, zipfian),
+            "f" => (0.5, 0.5, zipfian),
+            _ => return None,
+        };
+        Some(Self {
+            read_ratio,
+            write_ratio,
+            key_distribution,
+            insert_new_keys: matches!(name, "d" | "e"),
+            read_modify_write: name == "f",
+            ..Default::default()
+        })
+    }
+
+    /// Applies an option of the form `<name>=<value>` to the spec.
+    pub fn apply_option(&mut self, option: &str) -> Result<(), String> {
+        let (name, value) = option.split_once('=').ok_or(format!(
+            "Invalid workload option {option}, format is <name>=<value>"
+        ))?;
+        let invalid = || format!("Invalid value for workload option {name}: {value}");
+        match name {
+            "read" => self.read_ratio = value.parse().map_err(|_| invalid())?,
+            "write" => self.write_ratio = value.parse().map_err(|_| invalid())?,
+            "delete" => self.delete_ratio = value.parse().map_err(|_| invalid())?,
+            "keys" => self.key_space = value.parse().map_err(|_| invalid())?,
+            "dist" => {
+                self.key_distribution = match value {
+                    "uniform" => KeyDistribution::Uniform,
+                    "zipfian" => KeyDistribution::Zipfian {
+                        theta: DEFAULT_ZIPFIAN_THETA,
+                    },
+                    "hotspot" => KeyDistribution::Hotspot {
+                        hot_fraction: 0.2,
+                        hot_op_fraction: 0.8,
+                    },
+                    "latest" => KeyDistribution::Latest,
+                    _ => return Err(invalid()),
+                }
+            }
+            "theta" => {
+                self.key_distribution = KeyDistribution::Zipfian {
+                    theta: value.parse().map_err(|_| invalid())?,
+                }
+            }
+            "value" => {
+                self.value_size = match value.split_once('-') {
+                    Some((min, max)) => ValueSize::Uniform {
+                        min: min.parse().map_err(|_| invalid())?,
+                        max: max.parse().map_err(|_| invalid())?,
+                    },
+                    None => ValueSize::Fixed {
+                        size: value.parse().map_err(|_| invalid())?,
+                    },
+                }
+            }
+            "ops" => {
+                self.ops = Some(value.parse().map_err(|_| invalid())?);
+                self.duration_secs = None;
+            }
+            "duration" => {
+                self.duration_secs = Some(parse_duration(value).ok_or_else(invalid)?.as_secs_f64());
+                self.ops = None;
+            }
+            "seed" => self.seed = Some(value.parse().map_err(|_| invalid())?),
+            _ => return Err(format!("Unknown workload option {name}")),
+        }
+        self.validate()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let total_ratio = self.read_ratio + self.write_ratio + self.delete_ratio;
+        if self.read_ratio < 0.0 || self.write_ratio < 0.0 || self.delete_ratio < 0.0 {
+            return Err("Workload ratios must not be negative".to_string());
+        }
+        if total_ratio <= 0.0 {
+            return Err("At least one workload ratio must be positive".to_string());
+        }
+        if self.key_space == 0 {
+            return Err("Workload key space must not be empty".to_string());
+        }
+        if self.key_space > MAX_KEY_SPACE {
+            return Err(format!(
+                "Workload key space must not exceed {MAX_KEY_SPACE} keys"
+            ));
+        }
+        if let ValueSize::Uniform { min, max } = self.value_size {
+            if min > max {
+                return Err("Minimum value size must not exceed the maximum".to_string());
+            }
+        }
+        if let KeyDistribution::Zipfian { theta } = self.key_distribution {
+            if !(0.0..1.0).contains(&theta) || theta == 0.0 {
+                return Err("Zipfian theta must be in (0, 1)".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn describe(&self) -> String {
+        let limit = match (self.ops, self.duration_secs) {
+            (_, Some(secs)) => format!("for {secs}s"),
+            (Some(ops), None) => format!("{ops} ops"),
+            (None, None) => "until stopped".to_string(),
+        };
+        format!(
+            "read/write/delete {}/{}/{}, {} keys, {:?}, values {:?}, {limit}",
+            self.read_ratio,
+            self.write_ratio,
+            self.delete_ratio,
+            self.key_space,
+            self.key_distribution,
+            self.value_size
+        )
+    }
+}
+
+/// The zipfian generator from YCSB (Gray et al., "Quickly generating billion-record synthetic
+/// databases"). Returns item 0 most often.
+struct Zipfian {
+    items: u64,
+    theta: f64,
+    alpha: f64,
+    zetan: f64,
+    eta: f64,
+}
+
+/// The generalized harmonic number `sum(1 / i^theta)` for `i` in `1..=n`. Beyond the first
+/// terms, the sum is approximated by the integral of `x^-theta`, which keeps large key spaces
+/// from stalling the coordinator.
+fn zeta(n: u64, theta: f64) -> f64 {
+    let exact_terms = n.min(ZETA_EXACT_TERMS);
+    let exact = (1..=exact_terms)
+        .map(|i| 1.0 / (i as f64).powf(theta))
+        .sum::<f64>();
+    if n == exact_terms {
+        return exact;
+    }
+    let antiderivative = |x: f64| x.powf(1.0 - theta) / (1.0 - theta);
+    exact + antiderivative(n as f64 + 0.5) - antiderivative(exact_terms as f64 + 0.5)
+}
+
+impl Zipfian {
+    fn new(items: u64, theta: f64) -> Self {
+        let zetan = zeta(items, theta);
+        let zeta2 = zeta(2.min(items), theta);
+        Self {
+            items,
+            theta,
+            alpha: 1.0 / (1.0 - theta),
+            zetan,
+            eta: (1.0 - (2.0 / items as f64).powf(1.0 - theta)) / (1.0 - zeta2 / zetan),
+        }
+    }
+
+    fn next(&self, rng: &mut StdRng) -> u64 {
+        let u: f64 = rng.gen();
+        let uz = u * self.zetan;
+        if uz < 1.0 {
+            return 0;
+        }
+        if uz < 1.0 + 0.5_f64.powf(self.theta) {
+            return 1.min(self.items - 1);
+        }
+        let item = self.items as f64 * (self.eta * u - self.eta + 1.0).powf(self.alpha);
+        (item as u64).min(self.items - 1)
+    }
+}
+
+/// Generates the commands of a workload.
+pub struct WorkloadGenerator {
+    spec: WorkloadSpec,
+    rng: StdRng,
+    zipfian: Option<Zipfian>,
+    /// Number of keys in use, grows when inserting new keys.
+    num_keys: u64,
+}
+
+impl WorkloadGenerator {
+    pub fn new(spec: WorkloadSpec) -> Self {
+        let rng = match spec.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let zipfian = match spec.key_distribution {
+            KeyDistribution::Zipfian { theta } => Some(Zipfian::new(spec.key_space, theta)),
+            KeyDistribution::Latest => Some(Zipfian::new(spec.key_space, DEFAULT_ZIPFIAN_THETA)),
+            _ => None,
+        };
+        Self {
+            num_keys: spec.key_space,
+            spec,
+            rng,
+            zipfian,
+        }
+    }
+
+    fn next_key(&mut self) -> String {
+        let key_space = self.spec.key_space;
+        let key = match self.spec.key_distribution {
+            KeyDistribution::Uniform => self.rng.gen_range(0..self.num_keys),
+            KeyDistribution::Zipfian { .. } => self.zipfian.as_ref().unwrap().next(&mut self.rng),
+            KeyDistribution::Hotspot {
+                hot_fraction,
+                hot_op_fraction,
+            } => {
+                let hot_keys = ((key_space as f64 * hot_fraction) as u64).clamp(1, key_space);
+                if hot_keys == key_space || self.rng.gen_bool(hot_op_fraction.clamp(0.0, 1.0)) {
+                    self.rng.gen_range(0..hot_keys)
+                } else {
+                    self.rng.gen_range(hot_keys..key_space)
+                }
+            }
+            KeyDistribution::Latest => {
+                let offset = self.zipfian.as_ref().unwrap().next(&mut self.rng);
+                self.num_keys - 1 - offset.min(self.num_keys - 1)
+            }
+        };
+        format!("{WORKLOAD_KEY_PREFIX}{key}")
+    }
+
+    fn next_value(&mut self) -> String {
+        let size = match self.spec.value_size {
+            ValueSize::Fixed { size } => size,
+            ValueSize::Uniform { min, max } => self.rng.gen_range(min..=max),
+        };
+        Alphanumeric.sample_string(&mut self.rng, size)
+    }
+
+    /// Returns the commands of the next operation. Read-modify-writes consist of two commands.
+    pub fn next_operation(&mut self) -> Vec<KVCommand> {
+        let spec = &self.spec;
+        let total_ratio = spec.read_ratio + spec.write_ratio + spec.delete_ratio;
+        let (write_ratio, delete_ratio) = (spec.write_ratio, spec.delete_ratio);
+        let (insert_new_keys, read_modify_write) = (spec.insert_new_keys, spec.read_modify_write);
+        let op = self.rng.gen_range(0.0..total_ratio);
+        if op < write_ratio {
+            let key = if insert_new_keys {
+                self.num_keys += 1;
+                format!("{WORKLOAD_KEY_PREFIX}{}", self.num_keys - 1)
+            } else {
+                self.next_key()
+            };
+            let value = self.next_value();
+            let put = KVCommand::Put(KeyValue {
+                key: key.clone(),
+                value,
+            });
+            if read_modify_write {
+                vec![KVCommand::Get(key), put]
+            } else {
+                vec![put]
+            }
+        } else if op < write_ratio + delete_ratio {
+            vec![KVCommand::Delete(self.next_key())]
+        } else {
+            vec![KVCommand::Get(self.next_key())]
+        }
+    }
+}
+
+/// Feeds a workload into the command queue until its operation count or duration is reached.
+pub struct WorkloadRunner {
+    io_sender: Sender<IOMessage>,
+    cmd_queue: CommandQueue,
+    generator: WorkloadGenerator,
+    ops: Option<u64>,
+    duration: Option<Duration>,
+}
+
+impl WorkloadRunner {
+    pub fn new(spec: WorkloadSpec, io_sender: Sender<IOMessage>, cmd_queue: CommandQueue) -> Self {
+        Self {
+            io_sender,
+            cmd_queue,
+            ops: spec.ops,
+            duration: spec.duration_secs.map(Duration::from_secs_f64),
+            generator: WorkloadGenerator::new(spec),
+        }
+    }
+
+    pub async fn run(&mut self) {
+        let start = Instant::now();
+        let mut issued_ops = 0;
+        let ops_left = |issued_ops: u64, ops: Option<u64>| match ops {
+            Some(ops) => issued_ops < ops,
+            None => true,
+        };
+        let mut interval = tokio::time::interval(PROPOSE_TICK_RATE);
+        loop {
+            interval.tick().await;
+            let time_is_up = self.duration.is_some_and(|d| start.elapsed() >= d);
+            if !ops_left(issued_ops, self.ops) || time_is_up {
+                break;
+            }
+            // Keep the queue filled, but don't generate the whole workload upfront
+            let mut queue = self.cmd_queue.lock().await;
+            while queue.len() < WORKLOAD_QUEUE_TARGET && ops_left(issued_ops, self.ops) {
+                for cmd in self.generator.next_operation() {
+                    queue.push_front((cmd, None, CommandSource::Workload));
+                }
+                issued_ops += 1;
+            }
+        }
+        self.io_sender
+            .send(IOMessage::UIMessage(UIMessage::WorkloadFinished(
+                issued_ops,
+            )))
+            .await
+            .unwrap();
+    }
+}
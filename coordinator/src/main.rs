@@ -1,5 +1,5 @@
-use tokio::join;
-use tokio::sync::mpsc;
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
 
 use crate::messages::{coordinator::CDMessage, ui::UIMessage, IOMessage};
 use crate::utils::*;
@@ -7,9 +7,11 @@ use crate::utils::*;
 #[macro_use]
 extern crate lazy_static;
 
+mod bench;
 mod controller;
 mod coordinator;
 mod messages;
+mod telemetry;
 mod ui;
 mod utils;
 
@@ -20,20 +22,49 @@ async fn main() {
         default_panic(info);
         std::process::exit(1);
     }));
+    let trace_file = get_arg_value("--trace-file");
+    let trace_guard = telemetry::init(trace_file.as_deref());
     let (io_sender, io_receiver) = mpsc::channel::<IOMessage>(CHANNEL_BUFFER_SIZE);
     let (cd_sender, cd_receiver) = mpsc::channel::<CDMessage>(CHANNEL_BUFFER_SIZE);
-    let ui = ui::UI::new(io_sender.clone());
     let mut cd = coordinator::Coordinator::new(cd_receiver, io_sender.clone());
-    let mut controller = controller::Controller::new(ui, io_receiver, cd_sender);
+    let mode = std::env::args().nth(1);
+    let mut init_message = CDMessage::Initialize;
+    let frontend = if mode.as_deref() == Some("bench") {
+        let config = bench::BenchConfig::from_args().unwrap_or_else(|e| {
+            eprintln!("{e}");
+            std::process::exit(2);
+        });
+        let results = Arc::new(Mutex::new(bench::BenchResults::default()));
+        let mut runner = bench::BenchRunner::new(config, io_sender.clone(), results.clone());
+        tokio::spawn(async move { runner.run().await });
+        controller::Frontend::Bench(bench::BenchRecorder::new(results))
+    } else {
+        if mode.as_deref() == Some("replay") {
+            let trace_file = std::env::args().nth(2).unwrap_or_else(|| {
+                eprintln!("usage: app replay <trace-file>");
+                std::process::exit(2);
+            });
+            init_message = CDMessage::StartReplay(trace_file);
+        }
+        let ui = ui::UI::new(io_sender.clone());
+        io_sender
+            .send(IOMessage::UIMessage(UIMessage::Initialize))
+            .await
+            .unwrap();
+        controller::Frontend::Tui(ui)
+    };
+    let mut controller = controller::Controller::new(frontend, io_receiver, cd_sender);
 
     io_sender
-        .send(IOMessage::UIMessage(UIMessage::Initialize))
-        .await
-        .unwrap();
-    io_sender
-        .send(IOMessage::CDMessage(CDMessage::Initialize))
+        .send(IOMessage::CDMessage(init_message))
         .await
         .unwrap();
 
-    join!(cd.run(), controller.run());
+    tokio::select! {
+        _ = cd.run() => (),
+        _ = controller.run() => (),
+    }
+    // Flush the trace file, exiting the process doesn't run destructors
+    drop(trace_guard);
+    std::process::exit(0);
 }
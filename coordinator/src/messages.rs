@@ -2,18 +2,58 @@ use crate::messages::coordinator::CDMessage;
 use crate::messages::ui::UIMessage;
 
 pub mod coordinator {
+    use crate::coordinator::chaos::{ChaosAction, ChaosConfig};
+    use crate::coordinator::filter::FilterRule;
+    use crate::coordinator::freeze::Freeze;
+    use crate::coordinator::proposal_streamer::ProposerSetting;
+    use crate::coordinator::replay::ReplayControl;
+    use crate::coordinator::workload::WorkloadSpec;
     use crate::coordinator::KeyValue;
     use serde::{Deserialize, Serialize};
 
     #[derive(Debug, Clone)]
     pub enum CDMessage {
         StartBatchingPropose(u64),
+        StartWorkload(WorkloadSpec),
+        StopWorkload,
+        StartChaos(ChaosConfig),
+        StopChaos,
+        ApplyChaos(u64, ChaosAction),
+        SetInspection(bool),
+        /// Cuts all links between nodes of different groups. Nodes in no group form a group.
+        Partition(Vec<Vec<u64>>),
+        /// Restores all links. Delays and bandwidth limits are kept.
+        Heal,
+        /// Removes the delays added to links by the chaos mode.
+        ClearDelays,
+        /// Pauses a node or cuts its client link, or restores all its links.
+        SetFreeze(u64, Option<Freeze>),
+        /// Limits the bytes per second sent from a node to another, or removes the limit.
+        SetBandwidth(u64, u64, Option<u64>),
+        AddFilter(FilterRule),
+        RemoveFilter(u64),
+        ClearFilters,
+        ListFilters,
+        /// Starts or stops scanning the databases of the nodes for the KV browser.
+        BrowseKeys(bool),
+        ScanResponse(u64, Vec<KeyValue>),
+        StartReplay(String),
+        ReplayControl(ReplayControl),
         Initialize, // Launch to initialize the application
         KVCommand(KVCommand, Option<u64>),
         SetConnection(u64, Option<u64>, bool),
         OmnipaxosNodeCrashed(u64),
         OmnipaxosNodeJoined(u64),
         NewRound(u64, Option<Round>),
+        Decided(u64, u64),
+        /// Ids of the requests a node reported as decided.
+        DecidedRequests(u64, Vec<u64>),
+        /// A value read at a node, with the id of the request that read it.
+        ReadResponse(u64, String, Option<String>, Option<u64>),
+        CheckHistory,
+        ClearHistory,
+        SetProposer(ProposerSetting),
+        NodeStatus(u64, NodeStatus),
         Scenario(String),
     }
 
@@ -31,23 +71,62 @@ pub mod coordinator {
         pub leader: u64,
     }
 
+    /// Same as in KV demo
+    #[derive(Clone, Copy, Eq, Debug, PartialEq, Serialize, Deserialize)]
+    pub enum Role {
+        Leader,
+        Follower,
+    }
+
+    /// Same as in KV demo
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct NodeStatus {
+        pub promise: Option<Round>,
+        pub accepted_idx: u64,
+        pub decided_idx: u64,
+        pub compacted_idx: u64,
+        pub role: Role,
+        /// Peers the node has received heartbeats from in the last heartbeat round.
+        pub connected_peers: Vec<u64>,
+        /// Number of entries in the log that are not compacted.
+        pub log_size: u64,
+    }
+
     /// Same as in KV demo
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub enum APIResponse {
         Decided(u64),
-        Get(String, Option<String>),
+        /// Ids of the requests whose commands were just decided.
+        DecidedRequests(Vec<u64>),
+        /// The value of a key, with the id of the request that read it.
+        Get(String, Option<String>, Option<u64>),
         NewRound(Option<Round>),
+        Status(NodeStatus),
+        Scan(Vec<KeyValue>),
     }
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub(crate) enum Message {
-        APIRequest(KVCommand),
+        /// A command with the id of the request, which is sent back when it is decided.
+        APIRequest(KVCommand, Option<u64>),
+        /// Asks for the first keys of the database, up to the given number, with their values.
+        ScanRequest(usize),
         APIResponse(APIResponse, u64),
     }
 }
 
 pub mod ui {
     use super::coordinator::APIResponse;
+    use crate::coordinator::bandwidth::LinkQueueStatus;
+    use crate::coordinator::chaos::{ChaosAction, ChaosConfig};
+    use crate::coordinator::downtime::DowntimeWindow;
+    use crate::coordinator::in_flight::FailedProposal;
+    use crate::coordinator::inspector::MessageFlowReport;
+    use crate::coordinator::kv_browser::KVComparison;
+    use crate::coordinator::latency::LatencyReport;
+    use crate::coordinator::linearizability::CheckReport;
+    use crate::coordinator::replay::ReplayStatus;
+    use crate::coordinator::timeline::ElectionRecord;
     use crate::coordinator::NetworkState;
 
     #[derive(Debug, Clone)]
@@ -61,6 +140,24 @@ pub mod ui {
         ClusterUnreachable,
         NoSuchNode(u64, Vec<u64>),
         ProposalStatus(u64),
+        ProposalsFailed(Vec<FailedProposal>),
+        ProposalsRetried(usize),
+        LinearizabilityCheck(CheckReport),
+        HistoryCleared,
+        ShowNodeStatus,
+        ElectionTimeline(Vec<ElectionRecord>),
+        Downtime(DowntimeWindow),
+        Latency(LatencyReport),
+        WorkloadStarted(String),
+        WorkloadFinished(u64),
+        WorkloadStopped,
+        ChaosStarted(ChaosConfig),
+        ChaosAction(u64, ChaosAction),
+        ChaosStopped,
+        ReplayStatus(ReplayStatus),
+        MessageFlow(Option<MessageFlowReport>),
+        LinkQueues(Vec<LinkQueueStatus>),
+        KVComparison(KVComparison),
         #[allow(dead_code)]
         Debug(String),
         Exit,
@@ -0,0 +1,26 @@
+use std::fs::OpenOptions;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::prelude::*;
+
+/// Sets up structured tracing for the coordinator. Since the TUI owns the terminal, events are
+/// only recorded when a trace file is given, in which case they are written to it as JSON lines.
+/// The returned guard must be kept alive for the file writer to flush.
+pub fn init(trace_file: Option<&str>) -> Option<WorkerGuard> {
+    let path = trace_file?;
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .expect("failed to open trace file");
+    let (writer, guard) = tracing_appender::non_blocking(file);
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .json()
+                .with_current_span(true)
+                .with_span_list(false)
+                .with_writer(writer),
+        )
+        .init();
+    Some(guard)
+}
@@ -1,4 +1,6 @@
-use crossterm::event::DisableMouseCapture;
+use crossterm::event::{
+    DisableMouseCapture, EnableMouseCapture, Event, MouseButton, MouseEvent, MouseEventKind,
+};
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode, LeaveAlternateScreen};
 use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
@@ -8,13 +10,19 @@ use tui_textarea::{Input, Key};
 
 use std::io::stdout;
 use std::sync::Arc;
+use std::time::Duration;
 
-use crate::messages::coordinator::APIResponse;
+use crate::coordinator::linearizability::CheckOutcome;
+use crate::messages::coordinator::{APIResponse, CDMessage};
 use crate::messages::{ui::UIMessage, IOMessage};
-use crate::ui::ui_app::cli::CLIHandler;
-use crate::ui::ui_app::render::render;
+use crate::ui::ui_app::cli::{send_through, CLIHandler};
+use crate::ui::ui_app::layout::{Tab, UILayout};
+use crate::ui::ui_app::render::{canvas_target, render, CanvasTarget};
 use crate::ui::ui_app::UIApp;
-use crate::utils::{UI_MAX_DECIDED_BARS, UI_TICK_RATE};
+use crate::utils::{
+    UI_KV_SCROLL_STEP, UI_MAX_DECIDED_BARS, UI_MAX_FAILURE_LOGS, UI_MAX_LATENCY_POINTS,
+    UI_SIDE_WIDTH_STEP, UI_TICK_RATE,
+};
 
 mod ui_app;
 
@@ -27,8 +35,9 @@ pub struct UI {
 impl UI {
     pub(crate) fn new(io_sender: Sender<IOMessage>) -> Self {
         // Configure Crossterm backend for tui
-        let stdout = stdout();
+        let mut stdout = stdout();
         enable_raw_mode().unwrap();
+        crossterm::execute!(stdout, EnableMouseCapture).unwrap();
         let backend = CrosstermBackend::new(stdout);
         let terminal = Terminal::new(backend).unwrap();
         Self {
@@ -60,7 +69,6 @@ impl UI {
                 .unwrap();
                 self.terminal.clear().unwrap();
                 self.terminal.show_cursor().unwrap();
-                std::process::exit(0);
             }
             UIMessage::OmnipaxosNetworkUpdate(mut network_statue) => {
                 network_statue.nodes.sort();
@@ -71,10 +79,10 @@ impl UI {
             UIMessage::OmnipaxosResponse(response, pid) => match response {
                 APIResponse::Decided(idx) => {
                     let mut ui_app = self.ui_app.lock().await;
-                    ui_app.progress.finished = idx - ui_app.progress.starting_idx;
+                    ui_app.progress.finished = idx.saturating_sub(ui_app.progress.starting_idx);
                     ui_app.decided_idx = idx;
                 }
-                APIResponse::Get(key, value) => {
+                APIResponse::Get(key, value, _) => {
                     {
                         let mut ui_app = self.ui_app.lock().await;
                         ui_app.append_log(format!("[Node {pid}] key: {key}, value: {:?}", value));
@@ -86,8 +94,11 @@ impl UI {
                     }
                     self.update_ui().await;
                 }
-                // Ignore this case. Will get notified in OmniPaxosNetworkUpdate instead
-                APIResponse::NewRound(_) => (),
+                // Ignore these cases. Will get notified in OmniPaxosNetworkUpdate instead
+                APIResponse::NewRound(_)
+                | APIResponse::Status(_)
+                | APIResponse::Scan(_)
+                | APIResponse::DecidedRequests(_) => (),
             },
             UIMessage::OmnipaxosNodeCrashed(id) => {
                 self.ui_app
@@ -100,7 +111,64 @@ impl UI {
                 self.ui_app
                     .lock()
                     .await
-                    .append_log(format!("Couldn't reach cluster"));
+                    .append_log("Couldn't reach cluster, holding proposals".to_string());
+                self.update_ui().await;
+            }
+            UIMessage::ProposalsFailed(failures) => {
+                let mut ui_app = self.ui_app.lock().await;
+                for failure in failures.iter().take(UI_MAX_FAILURE_LOGS) {
+                    ui_app.append_log(format!(
+                        "Proposal {:?} at node {} failed after {} attempt(s): {}",
+                        failure.cmd, failure.proposer, failure.attempts, failure.reason
+                    ));
+                }
+                if failures.len() > UI_MAX_FAILURE_LOGS {
+                    ui_app.append_log(format!(
+                        "... and {} more failed proposals",
+                        failures.len() - UI_MAX_FAILURE_LOGS
+                    ));
+                }
+                drop(ui_app);
+                self.update_ui().await;
+            }
+            UIMessage::LinearizabilityCheck(report) => {
+                let mut ui_app = self.ui_app.lock().await;
+                let summary = format!(
+                    "{} operations on {} keys, checked in {:.3}s",
+                    report.operations,
+                    report.keys,
+                    report.elapsed.as_secs_f64()
+                );
+                match report.outcome {
+                    CheckOutcome::Linearizable => {
+                        ui_app.append_log(format!("History is linearizable ({summary})"))
+                    }
+                    CheckOutcome::NotLinearizable { key, anomaly } => {
+                        ui_app.append_log(format!(
+                            "History is NOT linearizable ({summary}). Minimal anomaly on key {key}:"
+                        ));
+                        for op in anomaly {
+                            ui_app.append_log(format!("  {op}"));
+                        }
+                    }
+                    CheckOutcome::Unknown { key } => ui_app.append_log(format!(
+                        "Linearizability unknown ({summary}): search for key {key} exceeded its budget"
+                    )),
+                }
+                drop(ui_app);
+                self.update_ui().await;
+            }
+            UIMessage::HistoryCleared => {
+                self.ui_app
+                    .lock()
+                    .await
+                    .append_log("History cleared".to_string());
+                self.update_ui().await;
+            }
+            UIMessage::ProposalsRetried(count) => {
+                self.ui_app.lock().await.append_log(format!(
+                    "Retrying {count} unacknowledged proposals after the leader change"
+                ));
                 self.update_ui().await;
             }
             UIMessage::NoSuchNode(invalid_node_id, valid_node_ids) => {
@@ -127,6 +195,129 @@ impl UI {
                     ui_app.progress.is_ongoing = false;
                 }
             }
+            UIMessage::ShowNodeStatus => {
+                let mut ui_app = self.ui_app.lock().await;
+                let mut node_status = ui_app
+                    .network_state
+                    .node_status
+                    .iter()
+                    .map(|(pid, status)| (*pid, status.clone()))
+                    .collect::<Vec<_>>();
+                node_status.sort_by_key(|(pid, _)| *pid);
+                if node_status.is_empty() {
+                    ui_app.append_log("No status reported by any node yet".to_string());
+                }
+                for (pid, status) in node_status {
+                    ui_app.append_log(format!(
+                        "[Node {pid}] {:?}, promise: {:?}, accepted: {}, decided: {}, compacted: {}, log size: {}, connected to: {:?}",
+                        status.role,
+                        status.promise.map(|r| (r.round_num, r.leader)),
+                        status.accepted_idx,
+                        status.decided_idx,
+                        status.compacted_idx,
+                        status.log_size,
+                        status.connected_peers
+                    ));
+                }
+                drop(ui_app);
+                self.update_ui().await;
+            }
+            UIMessage::ElectionTimeline(records) => {
+                self.ui_app.lock().await.elections = records;
+                self.update_ui().await;
+            }
+            UIMessage::Latency(report) => {
+                let mut ui_app = self.ui_app.lock().await;
+                ui_app.latency = report.overall;
+                if let Some(interval) = report.interval {
+                    let as_ms = |latency: Duration| latency.as_secs_f64() * 1000.0;
+                    let ui_app = &mut *ui_app;
+                    for (data, latency) in [
+                        (&mut ui_app.latency_p50_data, interval.p50),
+                        (&mut ui_app.latency_p99_data, interval.p99),
+                    ] {
+                        data.push((report.elapsed, as_ms(latency)));
+                        if data.len() > UI_MAX_LATENCY_POINTS {
+                            data.remove(0);
+                        }
+                    }
+                }
+            }
+            UIMessage::Downtime(window) => {
+                let ended_by = match window.ended_by {
+                    Some(round) => format!(
+                        "ended by election of node {} in round {}",
+                        round.leader, round.round_num
+                    ),
+                    None => "no election".to_string(),
+                };
+                self.ui_app.lock().await.append_log(format!(
+                    "Downtime of {:.3}s from {} ({ended_by}), proposed {} ({} attempts) / decided {} during the window",
+                    window.duration.as_secs_f64(),
+                    window.start.format("%H:%M:%S%.3f"),
+                    window.proposed,
+                    window.attempts,
+                    window.decided
+                ));
+                self.update_ui().await;
+            }
+            UIMessage::WorkloadStarted(description) => {
+                self.ui_app
+                    .lock()
+                    .await
+                    .append_log(format!("Started workload: {description}"));
+                self.update_ui().await;
+            }
+            UIMessage::WorkloadFinished(num_ops) => {
+                self.ui_app
+                    .lock()
+                    .await
+                    .append_log(format!("Workload finished issuing {num_ops} operations"));
+                self.update_ui().await;
+            }
+            UIMessage::WorkloadStopped => {
+                self.ui_app
+                    .lock()
+                    .await
+                    .append_log("Workload stopped".to_string());
+                self.update_ui().await;
+            }
+            UIMessage::ChaosStarted(config) => {
+                self.ui_app.lock().await.append_log(format!(
+                    "Chaos started with seed {}, injecting a fault every {:.1}s (repeat with: chaos start --seed {} --interval {}ms)",
+                    config.seed,
+                    config.interval.as_secs_f64(),
+                    config.seed,
+                    config.interval.as_millis()
+                ));
+                self.update_ui().await;
+            }
+            UIMessage::ChaosAction(step, action) => {
+                self.ui_app
+                    .lock()
+                    .await
+                    .append_log(format!("Chaos #{step}: {action}"));
+                self.update_ui().await;
+            }
+            UIMessage::ChaosStopped => {
+                self.ui_app
+                    .lock()
+                    .await
+                    .append_log("Chaos stopped, network healed".to_string());
+                self.update_ui().await;
+            }
+            UIMessage::MessageFlow(report) => {
+                self.ui_app.lock().await.message_flow = report;
+            }
+            UIMessage::LinkQueues(statuses) => {
+                self.ui_app.lock().await.link_queues = statuses;
+            }
+            UIMessage::KVComparison(comparison) => {
+                self.ui_app.lock().await.kv_comparison = Some(comparison);
+            }
+            UIMessage::ReplayStatus(status) => {
+                self.ui_app.lock().await.replay = Some(status);
+            }
             UIMessage::Debug(string) => {
                 self.ui_app.lock().await.append_log(string);
                 self.update_ui().await;
@@ -137,6 +328,12 @@ impl UI {
     pub async fn start(&mut self) {
         self.terminal.clear().unwrap();
         self.terminal.hide_cursor().unwrap();
+        if self.ui_app.lock().await.layout.tab == Tab::KVBrowser {
+            self.io_sender
+                .send(IOMessage::CDMessage(CDMessage::BrowseKeys(true)))
+                .await
+                .unwrap();
+        }
         let ui_app = self.ui_app.clone();
         // Run input listener
         let io_sender = self.io_sender.clone();
@@ -157,8 +354,10 @@ impl UI {
     }
 
     async fn update_ui(&mut self) {
-        let ui_app = self.ui_app.lock().await;
-        self.terminal.draw(|rect| render(rect, &ui_app)).unwrap();
+        let mut ui_app = self.ui_app.lock().await;
+        self.terminal
+            .draw(|rect| render(rect, &mut ui_app))
+            .unwrap();
     }
 }
 
@@ -181,7 +380,8 @@ impl Ticker {
                 _ = ui_interval.tick() => {
                     counter += 1.0;
                     let mut ui_app = self.ui_app.lock().await;
-                    let num_decided = (ui_app.decided_idx - last_decided_idx) as f64;
+                    // The decided index goes back when seeking back in a replay
+                    let num_decided = ui_app.decided_idx.saturating_sub(last_decided_idx) as f64;
                     let round = if num_decided as u64 == 0 {
                         " ".to_string()
                     } else {
@@ -224,14 +424,89 @@ impl UserInputListener {
     pub async fn run(&mut self) {
         loop {
             if crossterm::event::poll(UI_TICK_RATE).unwrap() {
-                match crossterm::event::read().unwrap().into() {
+                let input = match crossterm::event::read().unwrap() {
+                    Event::Mouse(mouse) => {
+                        self.handle_mouse(mouse).await;
+                        continue;
+                    }
+                    event => event.into(),
+                };
+                match input {
                     Input { key: Key::Esc, .. } => {
+                        let mut ui_app = self.ui_app.lock().await;
+                        if ui_app.target_node.take().is_some() {
+                            self.io_sender
+                                .send(IOMessage::UIMessage(UIMessage::UpdateUi))
+                                .await
+                                .unwrap();
+                            continue;
+                        }
                         self.io_sender
                             .send(IOMessage::UIMessage(UIMessage::Exit))
                             .await
                             .unwrap();
                         break;
                     }
+                    Input { key: Key::Tab, .. } => {
+                        self.update_layout(|layout| layout.tab = layout.tab.next())
+                            .await;
+                    }
+                    Input { key: Key::F(n), .. }
+                        if (1..=Tab::ALL.len()).contains(&(n as usize)) =>
+                    {
+                        self.update_layout(|layout| layout.tab = Tab::ALL[n as usize - 1])
+                            .await;
+                    }
+                    Input {
+                        key: Key::Left,
+                        alt: true,
+                        ..
+                    } => {
+                        self.update_layout(|layout| layout.resize_side(-UI_SIDE_WIDTH_STEP))
+                            .await;
+                    }
+                    Input {
+                        key: Key::Right,
+                        alt: true,
+                        ..
+                    } => {
+                        self.update_layout(|layout| layout.resize_side(UI_SIDE_WIDTH_STEP))
+                            .await;
+                    }
+                    Input {
+                        key: Key::Up,
+                        alt: true,
+                        ..
+                    } => {
+                        self.update_layout(|layout| layout.resize_charts(-1)).await;
+                    }
+                    Input {
+                        key: Key::Down,
+                        alt: true,
+                        ..
+                    } => {
+                        self.update_layout(|layout| layout.resize_charts(1)).await;
+                    }
+                    Input {
+                        key: Key::PageUp, ..
+                    } => {
+                        let mut ui_app = self.ui_app.lock().await;
+                        ui_app.kv_scroll = ui_app.kv_scroll.saturating_sub(UI_KV_SCROLL_STEP);
+                        self.io_sender
+                            .send(IOMessage::UIMessage(UIMessage::UpdateUi))
+                            .await
+                            .unwrap();
+                    }
+                    Input {
+                        key: Key::PageDown, ..
+                    } => {
+                        let mut ui_app = self.ui_app.lock().await;
+                        ui_app.kv_scroll += UI_KV_SCROLL_STEP;
+                        self.io_sender
+                            .send(IOMessage::UIMessage(UIMessage::UpdateUi))
+                            .await
+                            .unwrap();
+                    }
                     Input { key: Key::Up, .. } => {
                         let mut ui_app = self.ui_app.lock().await;
                         ui_app.scroll -= 1;
@@ -263,7 +538,10 @@ impl UserInputListener {
                         key: Key::Enter, ..
                     } => {
                         let mut ui_app = self.ui_app.lock().await;
-                        let log = ui_app.input_area.lines()[0].clone();
+                        let mut log = ui_app.input_area.lines()[0].clone();
+                        if let Some(pid) = ui_app.target_node.take() {
+                            log = send_through(log, pid);
+                        }
                         let out = self.cli_handler.handle_user_input(log).await;
                         if !out.is_empty() {
                             ui_app.append_log(out);
@@ -287,4 +565,67 @@ impl UserInputListener {
             }
         }
     }
+
+    async fn update_layout(&self, update: impl FnOnce(&mut UILayout)) {
+        let mut ui_app = self.ui_app.lock().await;
+        let old_tab = ui_app.layout.tab;
+        ui_app.update_layout(update);
+        let tab = ui_app.layout.tab;
+        drop(ui_app);
+        // The databases are only scanned while the KV browser is shown
+        if (old_tab == Tab::KVBrowser) != (tab == Tab::KVBrowser) {
+            self.io_sender
+                .send(IOMessage::CDMessage(CDMessage::BrowseKeys(
+                    tab == Tab::KVBrowser,
+                )))
+                .await
+                .unwrap();
+        }
+        self.io_sender
+            .send(IOMessage::UIMessage(UIMessage::UpdateUi))
+            .await
+            .unwrap();
+    }
+
+    /// Left-clicking a link cuts or restores it, left-clicking a node isolates or reconnects
+    /// it, and right-clicking a node sends the next get or put through it.
+    async fn handle_mouse(&mut self, mouse: MouseEvent) {
+        let MouseEventKind::Down(button) = mouse.kind else {
+            return;
+        };
+        let mut ui_app = self.ui_app.lock().await;
+        let Some(target) = canvas_target(&ui_app, mouse.column, mouse.row) else {
+            return;
+        };
+        let network_state = &ui_app.network_state;
+        let command = match (button, target) {
+            (MouseButton::Left, CanvasTarget::Link(a, b)) => {
+                format!("connection {a} {b} {}", network_state.is_cut(a, b))
+            }
+            (MouseButton::Left, CanvasTarget::Node(pid)) => {
+                let isolated = network_state
+                    .nodes
+                    .iter()
+                    .filter(|&&other| other != pid)
+                    .all(|&other| network_state.is_cut(pid, other));
+                format!("connection {pid} {isolated}")
+            }
+            (MouseButton::Right, CanvasTarget::Node(pid)) => {
+                ui_app.target_node = Some(pid);
+                self.io_sender
+                    .send(IOMessage::UIMessage(UIMessage::UpdateUi))
+                    .await
+                    .unwrap();
+                return;
+            }
+            _ => return,
+        };
+        let out = self.cli_handler.handle_user_input(command).await;
+        ui_app.append_log(out);
+        ui_app.scroll = 0;
+        self.io_sender
+            .send(IOMessage::UIMessage(UIMessage::UpdateUi))
+            .await
+            .unwrap();
+    }
 }
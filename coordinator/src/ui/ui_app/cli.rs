@@ -1,14 +1,23 @@
 use std::fmt;
 use std::str::Split;
 
+use crate::coordinator::chaos::ChaosConfig;
+use crate::coordinator::filter::{parse_link, FilterAction, FilterRule};
+use crate::coordinator::freeze::Freeze;
+use crate::coordinator::partition::{format_groups, parse_groups};
+use crate::coordinator::proposal_streamer::{LoopMode, ProposerSetting, RoutingPolicy};
+use crate::coordinator::replay::{ReplayControl, ReplaySeek};
+use crate::coordinator::workload::WorkloadSpec;
 use crate::coordinator::KeyValue;
 use crate::messages::coordinator::{CDMessage, KVCommand};
 use crate::messages::ui::UIMessage;
 use crate::messages::IOMessage;
+use crate::utils::{format_bandwidth, parse_bandwidth, parse_duration, DEFAULT_CHAOS_INTERVAL};
 
 use tokio::sync::mpsc::Sender;
 
-const INVALID_COMMAND: &str = "Valid commands are put/get/delete/connection/batch/scenario/clear";
+const INVALID_COMMAND: &str =
+    "Valid commands are put/get/delete/connection/partition/heal/pause/resume/isolate-client/batch/workload/rate/route/timeout/scenario/link/chaos/inspect/filter/replay/check/status/clear";
 const INVALID_DELETE: &str = "Invalid command, format is: delete <key-to-delete> [<node-id>]";
 const INVALID_GET: &str = "Invalid command, format is: get <key-to-get> [<node-id>]";
 const INVALID_PUT: &str = "Invalid command, format is: put <key> <value> [<node-id>]";
@@ -18,8 +27,33 @@ const INVALID_CONNECTION_ARG1: &str = "Invalid command: first connection argumen
 const INVALID_CONNECTION_ARG2: &str =
     "Invalid command: second connection argument must be a number";
 const INVALID_CONNECTION_ARG3: &str = "Invalid command: third connection argument must be a bool";
+const INVALID_PARTITION: &str =
+    "Invalid command, format is: partition {<node-id>,...} {<node-id>,...} ..., e.g. partition {1,2} {3,4,5}";
+const INVALID_FREEZE: &str =
+    "Invalid command, format is: pause <node-id>, resume <node-id> or isolate-client <node-id>";
 const INVALID_BATCH: &str = "Invalid command, format is: batch <number-of-proposals>";
 const INVALID_BATCH_ARG1: &str = "Invalid command: first batch argument must be a number";
+const INVALID_WORKLOAD: &str = "Invalid command, format is: workload <a-f/custom> [<option>=<value> ...] or workload stop. \
+    Options are read, write, delete, keys, dist (uniform/zipfian/hotspot/latest), theta, value (<size> or <min>-<max>), ops, duration (e.g. 30s) and seed";
+const INVALID_RATE: &str =
+    "Invalid command, format is: rate <ops-per-second>/s, rate max, rate open, \
+    rate closed <outstanding-requests> or rate batch <commands-per-write>";
+const INVALID_ROUTE: &str =
+    "Invalid command, format is: route <leader/round-robin/random/least-outstanding>";
+const INVALID_TIMEOUT: &str = "Invalid command, format is: timeout <duration> (e.g. 5s)";
+const INVALID_LINK: &str =
+    "Invalid command, format is: link <from-node-id> <to-node-id> bw <bandwidth> \
+    (e.g. 1MB/s, 500KB/s), link <from-node-id> <to-node-id> bw off or link delays off";
+const INVALID_CHAOS: &str =
+    "Invalid command, format is: chaos start [--seed <seed>] [--interval <duration>] or chaos stop";
+const INVALID_REPLAY: &str = "Invalid command, format is: replay play, replay pause, \
+    replay seek <time>, replay seek +<duration>/-<duration> or replay speed <factor>";
+const INVALID_INSPECT: &str = "Invalid command, format is: inspect on or inspect off";
+const INVALID_FILTER: &str = "Invalid command, format is: \
+    filter add <from>-><to> <message-type> drop, filter add <from>-><to> <message-type> delay <duration>, \
+    filter del <id>, filter list or filter clear. Nodes can be * for any node, message types are \
+    ble, paxos, * or an OmniPaxos message like prepare, promise, acceptsync, acceptdecide, accepted or decide";
+const INVALID_CHECK: &str = "Invalid command, format is: check or check clear";
 const INVALID_SCENARIO: &str =
     "Invalid command, format is: scenario <restore/qloss/constrained/chained>";
 
@@ -46,6 +80,16 @@ impl CLIHandler {
     }
 }
 
+/// Sends a get or put through a node by adding the node to the command, unless the command
+/// already names a node. Other commands are left as they are.
+pub fn send_through(line: String, pid: u64) -> String {
+    let words: Vec<&str> = line.trim().split(' ').collect();
+    match words[..] {
+        ["get", _] | ["put", _, _] => format!("{} {pid}", line.trim()),
+        _ => line,
+    }
+}
+
 struct ParseCommandError(String);
 impl fmt::Display for ParseCommandError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -82,6 +126,212 @@ fn parse_command(line: String) -> Result<(IOMessage, String), ParseCommandError>
             IOMessage::UIMessage(UIMessage::ClearConsole),
             "clear".to_string(),
         ),
+        "status" => (
+            IOMessage::UIMessage(UIMessage::ShowNodeStatus),
+            "Status reported by nodes:".to_string(),
+        ),
+        "partition" => {
+            let groups = parse_groups(&words.collect::<Vec<_>>().join(" "))
+                .ok_or(ParseCommandError(INVALID_PARTITION.to_string()))?;
+            let output = format!("Partitioning the network into {}", format_groups(&groups));
+            (IOMessage::CDMessage(CDMessage::Partition(groups)), output)
+        }
+        "heal" => (
+            IOMessage::CDMessage(CDMessage::Heal),
+            "Restoring all links".to_string(),
+        ),
+        "pause" | "resume" | "isolate-client" => {
+            let pid: u64 = words
+                .next()
+                .and_then(|pid| pid.parse().ok())
+                .ok_or(ParseCommandError(INVALID_FREEZE.to_string()))?;
+            let (freeze, output) = match command_type {
+                "pause" => (Some(Freeze::Paused), format!("Pausing node {pid}")),
+                "isolate-client" => (
+                    Some(Freeze::ClientIsolated),
+                    format!("Cutting the client link of node {pid}"),
+                ),
+                _ => (None, format!("Resuming node {pid}")),
+            };
+            (
+                IOMessage::CDMessage(CDMessage::SetFreeze(pid, freeze)),
+                output,
+            )
+        }
+        "link" => {
+            let invalid = || ParseCommandError(INVALID_LINK.to_string());
+            let first = words.next().ok_or_else(invalid)?;
+            if first == "delays" {
+                if words.next() != Some("off") {
+                    return Err(invalid());
+                }
+                return Ok((
+                    IOMessage::CDMessage(CDMessage::ClearDelays),
+                    "Removing the delays of all links".to_string(),
+                ));
+            }
+            let from: u64 = first.parse().map_err(|_| invalid())?;
+            let to: u64 = words
+                .next()
+                .and_then(|pid| pid.parse().ok())
+                .ok_or_else(invalid)?;
+            if from == to || words.next() != Some("bw") {
+                return Err(invalid());
+            }
+            let rate = match words.next().ok_or_else(invalid)? {
+                "off" => None,
+                rate => Some(parse_bandwidth(rate).ok_or_else(invalid)?),
+            };
+            let output = match rate {
+                Some(rate) => format!(
+                    "Limiting the link from node {from} to node {to} to {}",
+                    format_bandwidth(rate)
+                ),
+                None => format!("Removing the bandwidth limit from node {from} to node {to}"),
+            };
+            (
+                IOMessage::CDMessage(CDMessage::SetBandwidth(from, to, rate)),
+                output,
+            )
+        }
+        "chaos" => match words.next() {
+            Some("start") => {
+                let mut config = ChaosConfig {
+                    seed: rand::random(),
+                    interval: DEFAULT_CHAOS_INTERVAL,
+                };
+                while let Some(option) = words.next().filter(|w| !w.is_empty()) {
+                    let value = words.next();
+                    match option {
+                        "--seed" => {
+                            config.seed = value
+                                .and_then(|s| s.parse().ok())
+                                .ok_or(ParseCommandError(INVALID_CHAOS.to_string()))?
+                        }
+                        "--interval" => {
+                            config.interval = value
+                                .and_then(parse_duration)
+                                .filter(|i| !i.is_zero())
+                                .ok_or(ParseCommandError(INVALID_CHAOS.to_string()))?
+                        }
+                        _ => return Err(ParseCommandError(INVALID_CHAOS.to_string())),
+                    }
+                }
+                (
+                    IOMessage::CDMessage(CDMessage::StartChaos(config)),
+                    format!("Starting chaos with seed {}", config.seed),
+                )
+            }
+            Some("stop") => (
+                IOMessage::CDMessage(CDMessage::StopChaos),
+                "Stopping chaos".to_string(),
+            ),
+            _ => return Err(ParseCommandError(INVALID_CHAOS.to_string())),
+        },
+        "inspect" => {
+            let (enabled, output) = match words.next() {
+                Some("on") => (true, "Decoding the messages between nodes"),
+                Some("off") => (false, "Stopped decoding the messages between nodes"),
+                _ => return Err(ParseCommandError(INVALID_INSPECT.to_string())),
+            };
+            (
+                IOMessage::CDMessage(CDMessage::SetInspection(enabled)),
+                output.to_string(),
+            )
+        }
+        "filter" => {
+            let invalid = || ParseCommandError(INVALID_FILTER.to_string());
+            match words.next() {
+                Some("add") => {
+                    let (from, to) = words.next().and_then(parse_link).ok_or_else(invalid)?;
+                    let kinds = words
+                        .next()
+                        .and_then(|k| k.parse().ok())
+                        .ok_or_else(invalid)?;
+                    let action = match words.next() {
+                        Some("drop") => FilterAction::Drop,
+                        Some("delay") => FilterAction::Delay(
+                            words.next().and_then(parse_duration).ok_or_else(invalid)?,
+                        ),
+                        _ => return Err(invalid()),
+                    };
+                    let rule = FilterRule {
+                        from,
+                        to,
+                        kinds,
+                        action,
+                    };
+                    (
+                        IOMessage::CDMessage(CDMessage::AddFilter(rule)),
+                        format!("Adding filter {rule}"),
+                    )
+                }
+                Some("del") => {
+                    let id = words
+                        .next()
+                        .and_then(|id| id.trim_start_matches('#').parse().ok())
+                        .ok_or_else(invalid)?;
+                    (
+                        IOMessage::CDMessage(CDMessage::RemoveFilter(id)),
+                        format!("Removing filter #{id}"),
+                    )
+                }
+                Some("list") => (
+                    IOMessage::CDMessage(CDMessage::ListFilters),
+                    "Listing filters".to_string(),
+                ),
+                Some("clear") => (
+                    IOMessage::CDMessage(CDMessage::ClearFilters),
+                    "Removing all filters".to_string(),
+                ),
+                _ => return Err(invalid()),
+            }
+        }
+        "replay" => {
+            let invalid = || ParseCommandError(INVALID_REPLAY.to_string());
+            let (control, output) = match words.next() {
+                Some("play") => (ReplayControl::Play, "Playing".to_string()),
+                Some("pause") => (ReplayControl::Pause, "Pausing".to_string()),
+                Some("seek") => {
+                    let target = words.next().ok_or_else(invalid)?;
+                    let seek = if let Some(by) = target.strip_prefix('+') {
+                        ReplaySeek::Forward(parse_duration(by).ok_or_else(invalid)?)
+                    } else if let Some(by) = target.strip_prefix('-') {
+                        ReplaySeek::Back(parse_duration(by).ok_or_else(invalid)?)
+                    } else {
+                        ReplaySeek::To(parse_duration(target).ok_or_else(invalid)?)
+                    };
+                    (ReplayControl::Seek(seek), format!("Seeking to {target}"))
+                }
+                Some("speed") => {
+                    let speed = words
+                        .next()
+                        .and_then(|s| s.trim_end_matches('x').parse::<f64>().ok())
+                        .filter(|s| *s > 0.0 && s.is_finite())
+                        .ok_or_else(invalid)?;
+                    (
+                        ReplayControl::Speed(speed),
+                        format!("Replaying at {speed}x speed"),
+                    )
+                }
+                _ => return Err(invalid()),
+            };
+            (
+                IOMessage::CDMessage(CDMessage::ReplayControl(control)),
+                output,
+            )
+        }
+        "check" => match words.next() {
+            None => (
+                IOMessage::CDMessage(CDMessage::CheckHistory),
+                "Checking the history for linearizability...".to_string(),
+            ),
+            Some("clear") => (
+                IOMessage::CDMessage(CDMessage::ClearHistory),
+                "Clearing the history".to_string(),
+            ),
+            _ => return Err(ParseCommandError(INVALID_CHECK.to_string())),
+        },
         "delete" => {
             let value = words
                 .next()
@@ -171,15 +421,109 @@ fn parse_command(line: String) -> Result<(IOMessage, String), ParseCommandError>
             let output = format!("Batching {} put operations", num_proposals);
             (msg, output)
         }
+        "workload" => {
+            let workload_type = words
+                .next()
+                .ok_or(ParseCommandError(INVALID_WORKLOAD.to_string()))?;
+            if workload_type == "stop" {
+                let msg = IOMessage::CDMessage(CDMessage::StopWorkload);
+                return Ok((msg, "Stopping workload".to_string()));
+            }
+            let mut spec = WorkloadSpec::preset(workload_type)
+                .ok_or(ParseCommandError(INVALID_WORKLOAD.to_string()))?;
+            for option in words.filter(|w| !w.is_empty()) {
+                spec.apply_option(option).map_err(ParseCommandError)?;
+            }
+            let output = format!("Starting workload {workload_type}");
+            let msg = IOMessage::CDMessage(CDMessage::StartWorkload(spec));
+            (msg, output)
+        }
+        "rate" => {
+            let setting = words
+                .next()
+                .ok_or(ParseCommandError(INVALID_RATE.to_string()))?;
+            let mut parse_number = || {
+                words
+                    .next()
+                    .and_then(|n| n.parse::<u64>().ok())
+                    .filter(|n| *n > 0)
+                    .ok_or(ParseCommandError(INVALID_RATE.to_string()))
+            };
+            let (setting, output) = match setting {
+                "max" => (
+                    ProposerSetting::Rate(None),
+                    "Proposing as fast as possible".to_string(),
+                ),
+                "open" => (
+                    ProposerSetting::Mode(LoopMode::Open),
+                    "Proposing in open loop".to_string(),
+                ),
+                "closed" => {
+                    let outstanding = parse_number()?;
+                    (
+                        ProposerSetting::Mode(LoopMode::Closed { outstanding }),
+                        format!("Proposing in closed loop with {outstanding} outstanding requests"),
+                    )
+                }
+                "batch" => {
+                    let size = parse_number()?;
+                    (
+                        ProposerSetting::WriteBatch(size as usize),
+                        format!("Sending up to {size} commands per write"),
+                    )
+                }
+                rate => {
+                    let rate = rate
+                        .trim_end_matches("/s")
+                        .parse::<u64>()
+                        .ok()
+                        .filter(|r| *r > 0)
+                        .ok_or(ParseCommandError(INVALID_RATE.to_string()))?;
+                    (
+                        ProposerSetting::Rate(Some(rate)),
+                        format!("Proposing at {rate} ops/s"),
+                    )
+                }
+            };
+            let msg = IOMessage::CDMessage(CDMessage::SetProposer(setting));
+            (msg, output)
+        }
+        "route" => {
+            let routing = match words.next() {
+                Some("leader") => RoutingPolicy::Leader,
+                Some("round-robin") => RoutingPolicy::RoundRobin,
+                Some("random") => RoutingPolicy::Random,
+                Some("least-outstanding") => RoutingPolicy::LeastOutstanding,
+                _ => return Err(ParseCommandError(INVALID_ROUTE.to_string())),
+            };
+            let output = format!("Routing proposals to {routing}");
+            let msg =
+                IOMessage::CDMessage(CDMessage::SetProposer(ProposerSetting::Routing(routing)));
+            (msg, output)
+        }
+        "timeout" => {
+            let timeout = words
+                .next()
+                .and_then(parse_duration)
+                .filter(|t| !t.is_zero())
+                .ok_or(ParseCommandError(INVALID_TIMEOUT.to_string()))?;
+            let output = format!(
+                "Failing proposals after {:.1}s without acknowledgement",
+                timeout.as_secs_f64()
+            );
+            let msg =
+                IOMessage::CDMessage(CDMessage::SetProposer(ProposerSetting::Timeout(timeout)));
+            (msg, output)
+        }
         "scenario" => {
             let scenario_type = words
                 .next()
                 .ok_or(ParseCommandError(INVALID_SCENARIO.to_string()))?;
             let output = match scenario_type {
-                "qloss" => format!("Creating quorum-loss scenario"),
-                "constrained" => format!("Creating constrained election scenario"),
-                "chained" => format!("Creating chained scenario"),
-                "restore" => format!("Restoring all connections"),
+                "qloss" => "Creating quorum-loss scenario".to_string(),
+                "constrained" => "Creating constrained election scenario".to_string(),
+                "chained" => "Creating chained scenario".to_string(),
+                "restore" => "Restoring all connections".to_string(),
                 _ => return Err(ParseCommandError(INVALID_SCENARIO.to_string())),
             };
             let msg = IOMessage::CDMessage(CDMessage::Scenario(scenario_type.to_string()));
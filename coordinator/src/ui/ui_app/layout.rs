@@ -0,0 +1,111 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::utils::{
+    get_arg_value, DEFAULT_UI_LAYOUT_FILE, UI_CHART_HEIGHT_RANGE, UI_SIDE_WIDTH_RANGE,
+};
+
+lazy_static! {
+    /// Where the layout of the TUI is saved when it changes.
+    static ref UI_LAYOUT_FILE: String =
+        get_arg_value("--ui-layout").unwrap_or(DEFAULT_UI_LAYOUT_FILE.to_string());
+}
+
+/// Views of the TUI, one per tab.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Tab {
+    /// Throughput, output and the canvas of the network.
+    #[default]
+    Overview,
+    /// Replication progress and status reported by each node.
+    Nodes,
+    /// Leader elections and latency over time.
+    Timeline,
+    /// Messages between the nodes and the queues of the throttled links.
+    Messages,
+    /// Keys and values in the database of each node.
+    KVBrowser,
+}
+
+impl Tab {
+    pub const ALL: [Tab; 5] = [
+        Tab::Overview,
+        Tab::Nodes,
+        Tab::Timeline,
+        Tab::Messages,
+        Tab::KVBrowser,
+    ];
+
+    pub fn index(&self) -> usize {
+        Tab::ALL.iter().position(|tab| tab == self).unwrap()
+    }
+
+    pub fn next(&self) -> Tab {
+        Tab::ALL[(self.index() + 1) % Tab::ALL.len()]
+    }
+}
+
+impl fmt::Display for Tab {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Tab::Overview => write!(f, "Overview"),
+            Tab::Nodes => write!(f, "Nodes"),
+            Tab::Timeline => write!(f, "Timeline"),
+            Tab::Messages => write!(f, "Messages"),
+            Tab::KVBrowser => write!(f, "KV Browser"),
+        }
+    }
+}
+
+/// The selected tab and the sizes of the resizable panes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UILayout {
+    pub tab: Tab,
+    /// Width of the output column, in percent of the window.
+    pub side_width: u16,
+    /// Height of the throughput and latency charts, in rows.
+    pub chart_height: u16,
+}
+
+impl Default for UILayout {
+    fn default() -> Self {
+        Self {
+            tab: Tab::Overview,
+            side_width: 30,
+            chart_height: 10,
+        }
+    }
+}
+
+impl UILayout {
+    /// Loads the saved layout, or the default one if there is none. Pane sizes out of range,
+    /// e.g. in a hand-edited file, are clamped.
+    pub fn load() -> Self {
+        let mut layout: Self = std::fs::read_to_string(UI_LAYOUT_FILE.as_str())
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        layout.resize_side(0);
+        layout.resize_charts(0);
+        layout
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(UI_LAYOUT_FILE.as_str(), json)
+            .map_err(|e| format!("Could not save the layout to {}: {e}", *UI_LAYOUT_FILE))
+    }
+
+    pub fn resize_side(&mut self, delta: i32) {
+        self.side_width = resize(self.side_width, delta, UI_SIDE_WIDTH_RANGE);
+    }
+
+    pub fn resize_charts(&mut self, delta: i32) {
+        self.chart_height = resize(self.chart_height, delta, UI_CHART_HEIGHT_RANGE);
+    }
+}
+
+fn resize(size: u16, delta: i32, (min, max): (u16, u16)) -> u16 {
+    (size as i32 + delta).clamp(min as i32, max as i32) as u16
+}
@@ -1,11 +1,22 @@
+use crate::coordinator::bandwidth::LinkQueueStatus;
+use crate::coordinator::inspector::MessageFlowReport;
+use crate::coordinator::kv_browser::KVComparison;
+use crate::coordinator::latency::LatencySummary;
+use crate::coordinator::replay::ReplayStatus;
+use crate::coordinator::timeline::ElectionRecord;
 use crate::coordinator::NetworkState;
 use crate::messages::IOMessage;
+use crate::ui::ui_app::layout::UILayout;
 use crate::utils::COLORS;
+use ratatui::layout::Rect;
 use ratatui::style::Color;
+use std::collections::HashMap;
+use std::time::Instant;
 use tokio::sync::mpsc::Sender;
 use tui_textarea::TextArea;
 
 pub mod cli;
+pub mod layout;
 pub mod render;
 
 pub struct Progress {
@@ -37,6 +48,33 @@ pub struct UIApp<'a> {
     pub nodes: Vec<Node>,
     pub leader: Option<Node>,
     pub(crate) throughput: f64,
+    /// Latency percentiles of all acknowledged proposals.
+    pub latency: LatencySummary,
+    /// Median and 99th percentile latency in ms over time, as (seconds since start, latency).
+    pub latency_p50_data: Vec<(f64, f64)>,
+    pub latency_p99_data: Vec<(f64, f64)>,
+    /// History of round changes, oldest first.
+    pub elections: Vec<ElectionRecord>,
+    /// Progress of the trace being replayed, if any.
+    pub replay: Option<ReplayStatus>,
+    /// Latest messages between nodes by type, if the proxy inspects them.
+    pub message_flow: Option<MessageFlowReport>,
+    /// Bandwidth limits and queued messages of the throttled links.
+    pub link_queues: Vec<LinkQueueStatus>,
+    /// When each node last crashed, recovered, was paused or resumed, or became or stopped
+    /// being the leader.
+    pub node_changes: HashMap<u64, Instant>,
+    /// When each link was last cut or restored, by the pair of nodes with the lower pid first.
+    pub link_changes: HashMap<(u64, u64), Instant>,
+    /// Where the canvas was last drawn, to find the nodes and links that are clicked.
+    pub canvas_area: Rect,
+    /// Node that was right-clicked, through which the next get or put is sent.
+    pub target_node: Option<u64>,
+    pub layout: UILayout,
+    /// Latest keys and values read from each node, while the KV browser is shown.
+    pub kv_comparison: Option<KVComparison>,
+    /// First row shown in the KV browser.
+    pub kv_scroll: usize,
 }
 
 impl<'a> UIApp<'a> {
@@ -58,6 +96,20 @@ impl<'a> UIApp<'a> {
             nodes: vec![],
             leader: None,
             throughput: 0.0,
+            latency: LatencySummary::default(),
+            latency_p50_data: vec![],
+            latency_p99_data: vec![],
+            elections: vec![],
+            replay: None,
+            message_flow: None,
+            link_queues: vec![],
+            node_changes: HashMap::new(),
+            link_changes: HashMap::new(),
+            canvas_area: Rect::default(),
+            target_node: None,
+            layout: UILayout::load(),
+            kv_comparison: None,
+            kv_scroll: 0,
         }
     }
 
@@ -73,8 +125,19 @@ impl<'a> UIApp<'a> {
         self.logs.clear();
     }
 
+    /// Changes the layout and saves it.
+    pub fn update_layout(&mut self, update: impl FnOnce(&mut UILayout)) {
+        let old_layout = self.layout;
+        update(&mut self.layout);
+        if self.layout != old_layout {
+            if let Err(e) = self.layout.save() {
+                self.append_log(e);
+            }
+        }
+    }
+
     pub fn set_network_state(&mut self, network_state: NetworkState) {
-        // set up nodes if first time
+        // set up nodes if first time, otherwise note what changed
         if self.nodes.is_empty() {
             for (idx, &pid) in network_state.nodes.iter().enumerate() {
                 self.nodes.push(Node {
@@ -82,6 +145,8 @@ impl<'a> UIApp<'a> {
                     color: COLORS[idx % COLORS.len()],
                 });
             }
+        } else {
+            self.record_changes(&network_state);
         }
         // set leader
         if let Some(round) = network_state.max_round {
@@ -94,4 +159,26 @@ impl<'a> UIApp<'a> {
         }
         self.network_state = network_state;
     }
+
+    fn record_changes(&mut self, network_state: &NetworkState) {
+        let now = Instant::now();
+        let node_state = |state: &NetworkState, pid: u64| {
+            (
+                state.alive_nodes.contains(&pid),
+                state.freezes.get(&pid).copied(),
+                matches!(state.max_round, Some(round) if round.leader == pid),
+            )
+        };
+        for (i, &pid) in network_state.nodes.iter().enumerate() {
+            if node_state(&self.network_state, pid) != node_state(network_state, pid) {
+                self.node_changes.insert(pid, now);
+            }
+            for &other in &network_state.nodes[i + 1..] {
+                if self.network_state.is_cut(pid, other) != network_state.is_cut(pid, other) {
+                    self.link_changes
+                        .insert((pid.min(other), pid.max(other)), now);
+                }
+            }
+        }
+    }
 }
@@ -7,40 +7,48 @@ use ratatui::widgets::{block::Block, BarChart, BorderType, Borders, Gauge, Parag
 use ratatui::Frame;
 use ratatui::{
     prelude::*,
-    widgets::{block::Title, *},
+    widgets::{
+        block::{Position, Title},
+        *,
+    },
 };
 use std::collections::HashMap;
 use std::f64::consts::PI;
+use std::time::Instant;
 
 use tui_textarea::TextArea;
 
+use crate::coordinator::inspector::{MessageFlowReport, MessageKind};
+use crate::coordinator::kv_browser::KVComparison;
+use crate::ui::ui_app::layout::Tab;
 use crate::ui::ui_app::UIApp;
 use crate::utils::{
-    UI_BARCHART_GAP, UI_BARCHART_WIDTH, UI_INPUT_AREA_TITLE, UI_LEADER_RECT_COLOR,
-    UI_OUTPUT_AREA_TITLE, UI_PROGRESS_BAR_TITLE, UI_THROUGHPUT_TITLE, UI_TITLE,
+    format_bandwidth, KV_SCAN_MAX_KEYS, UI_BARCHART_GAP, UI_BARCHART_WIDTH,
+    UI_CANDIDATE_RECT_COLOR, UI_CANVAS_X_BOUNDS, UI_CANVAS_Y_BOUNDS, UI_CHANGE_BLINK_RATE,
+    UI_CHANGE_HIGHLIGHT_COLOR, UI_CHANGE_HIGHLIGHT_TIME, UI_CRASHED_NODE_COLOR, UI_CUT_LINK_COLOR,
+    UI_INPUT_AREA_TITLE, UI_KV_BROWSER_TITLE, UI_LATENCY_CHART_TITLE, UI_LATENCY_STATS_TITLE,
+    UI_LEADER_RECT_COLOR, UI_LINK_QUEUES_TITLE, UI_MESSAGE_FLOW_TITLE, UI_NODE_STATUS_TITLE,
+    UI_OUTPUT_AREA_TITLE, UI_PARTITION_GROUP_COLORS, UI_PROGRESS_BAR_TITLE,
+    UI_REPLICATION_BAR_WIDTH, UI_REPLICATION_TITLE, UI_TABS_HINT, UI_THROUGHPUT_TITLE,
+    UI_TIMELINE_TITLE, UI_TITLE,
 };
 
 /// render ui components
-pub fn render<B>(rect: &mut Frame<B>, app: &UIApp)
+pub fn render<B>(rect: &mut Frame<B>, app: &mut UIApp)
 where
     B: Backend,
 {
     let size = rect.size();
-    let window_width: usize = size.width.into();
 
     // Vertical layout
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints(
             [
-                // Title
+                // Title & Tabs
                 Constraint::Length(3),
-                // Bar Chart
-                Constraint::Length(10),
-                // Progress Bar
-                Constraint::Length(3),
-                // Output & Connection Status
-                Constraint::Min(app.nodes.len() as u16),
+                // Selected view
+                Constraint::Min(0),
                 // Input
                 Constraint::Length(3),
             ]
@@ -48,64 +56,269 @@ where
         )
         .split(size);
 
-    // Title
-    let title = draw_title(app);
-    rect.render_widget(title, chunks[0]);
+    // Title & Tabs
+    let tabs = draw_tabs(app);
+    rect.render_widget(tabs, chunks[0]);
 
-    // Chart
-    // let tooltip =     Canvas::default()
-    //     .block(Block::default().borders(Borders::ALL).title("World"))
-    //     .paint(|ctx| {
-    //         ctx.print(
-    //             0.0,
-    //             0.0,
-    //             Span::styled("You are here", Style::default().fg(Color::Yellow)),
-    //         );
-    //     })
-    //     .x_bounds([0.0, 100.0])
-    //     .y_bounds([0.0, 100.0]);
-    // rect.render_widget(tooltip, chunks[1]);
+    // Selected view, only the overview has a canvas to click on
+    app.canvas_area = Rect::default();
+    match app.layout.tab {
+        Tab::Overview => render_overview(rect, app, chunks[1]),
+        Tab::Nodes => render_nodes(rect, app, chunks[1]),
+        Tab::Timeline => render_timeline(rect, app, chunks[1]),
+        Tab::Messages => render_messages(rect, app, chunks[1]),
+        Tab::KVBrowser => render_kv_browser(rect, app, chunks[1]),
+    }
+
+    // Input
+    let textarea = app.input_area.clone();
+    let input = draw_input(textarea, app.target_node);
+    rect.render_widget(input.widget(), chunks[2]);
+}
+
+/// Splits an area into the output column on the left and the rest of the view.
+fn split_side(app: &UIApp, area: Rect) -> (Rect, Rect) {
+    let side_width = app.layout.side_width;
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage(side_width),
+                Constraint::Percentage(100 - side_width),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+    (chunks[0], chunks[1])
+}
+
+fn render_output<B>(rect: &mut Frame<B>, app: &UIApp, area: Rect)
+where
+    B: Backend,
+{
+    let output = draw_output(app, area.height as i64 - 2, area.width as i64);
+    rect.render_widget(output, area);
+}
+
+fn render_overview<B>(rect: &mut Frame<B>, app: &mut UIApp, area: Rect)
+where
+    B: Backend,
+{
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                // Bar Chart
+                Constraint::Length(app.layout.chart_height),
+                // Progress Bar
+                Constraint::Length(3),
+                // Output & Connection Status
+                Constraint::Min(0),
+            ]
+            .as_ref(),
+        )
+        .split(area);
 
+    // Chart
     let chart_data: &Vec<(&str, u64)> = &app
         .decided_data
         .iter()
-        .take(window_width / (UI_BARCHART_WIDTH + UI_BARCHART_GAP) as usize)
+        .take(chunks[0].width as usize * 6 / 10 / (UI_BARCHART_WIDTH + UI_BARCHART_GAP) as usize)
         .map(|(s, num)| (s.as_str(), *num))
         .collect::<Vec<(&str, u64)>>();
+    let chart_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage(60),
+                Constraint::Min(20),
+                Constraint::Length(20),
+            ]
+            .as_ref(),
+        )
+        .split(chunks[0]);
     let chart = draw_chart(app, chart_data);
-    rect.render_widget(chart, chunks[1]);
+    rect.render_widget(chart, chart_chunks[0]);
+
+    // Latency
+    let latency_chart = draw_latency_chart(app);
+    rect.render_widget(latency_chart, chart_chunks[1]);
+    let latency_stats = draw_latency_stats(app);
+    rect.render_widget(latency_stats, chart_chunks[2]);
 
     // Progress Bar
     let progress_bar = draw_progress_bar(app);
-    rect.render_widget(progress_bar, chunks[2]);
+    rect.render_widget(progress_bar, chunks[1]);
 
     // Output & Status
-    let body_chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        // .constraints([Constraint::Length(50), Constraint::Min(10)].as_ref())
-        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
-        .split(chunks[3]);
+    let (side_area, canvas_area) = split_side(app, chunks[2]);
+    let left_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
+        .split(side_area);
 
     // Output
-    let output = draw_output(
-        app,
-        body_chunks[0].height as i64 - 2,
-        body_chunks[0].width as i64,
-    );
-    rect.render_widget(output, body_chunks[0]);
+    render_output(rect, app, left_chunks[0]);
+
+    // Election timeline
+    let timeline = draw_timeline(app, left_chunks[1].height as i64 - 2);
+    rect.render_widget(timeline, left_chunks[1]);
+
+    app.canvas_area = canvas_area;
+    render_canvas(rect, app, canvas_area);
+}
+
+fn render_nodes<B>(rect: &mut Frame<B>, app: &UIApp, area: Rect)
+where
+    B: Backend,
+{
+    let (side_area, main_area) = split_side(app, area);
+    render_output(rect, app, side_area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                // Replication progress per node
+                Constraint::Length(app.nodes.len() as u16 + 3),
+                // Status reported by each node
+                Constraint::Min(0),
+            ]
+            .as_ref(),
+        )
+        .split(main_area);
+    let replication_table = draw_replication_table(app);
+    rect.render_widget(replication_table, chunks[0]);
+    let node_status_table = draw_node_status_table(app);
+    rect.render_widget(node_status_table, chunks[1]);
+}
 
+fn render_timeline<B>(rect: &mut Frame<B>, app: &UIApp, area: Rect)
+where
+    B: Backend,
+{
+    let (side_area, main_area) = split_side(app, area);
+    render_output(rect, app, side_area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(main_area);
+    let timeline = draw_timeline(app, chunks[0].height as i64 - 2);
+    rect.render_widget(timeline, chunks[0]);
+    let latency_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(20), Constraint::Length(20)].as_ref())
+        .split(chunks[1]);
+    let latency_chart = draw_latency_chart(app);
+    rect.render_widget(latency_chart, latency_chunks[0]);
+    let latency_stats = draw_latency_stats(app);
+    rect.render_widget(latency_stats, latency_chunks[1]);
+}
+
+fn render_messages<B>(rect: &mut Frame<B>, app: &UIApp, area: Rect)
+where
+    B: Backend,
+{
+    let (side_area, main_area) = split_side(app, area);
+    render_output(rect, app, side_area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                // Message flow
+                Constraint::Min(0),
+                // Throttled links
+                Constraint::Length(app.link_queues.len() as u16 + 3),
+            ]
+            .as_ref(),
+        )
+        .split(main_area);
+    match &app.message_flow {
+        Some(report) => {
+            let kinds = message_flow_kinds(report);
+            let widths: Vec<Constraint> = std::iter::once(Constraint::Length(6))
+                .chain(kinds.iter().map(|_| Constraint::Length(8)))
+                .chain([Constraint::Length(8), Constraint::Length(8)])
+                .collect();
+            let message_flow = draw_message_flow(report, &kinds, &widths);
+            rect.render_widget(message_flow, chunks[0]);
+        }
+        None => {
+            let hint =
+                Paragraph::new("The messages are not inspected, turn it on with: inspect on")
+                    .style(Style::default().fg(Color::Gray))
+                    .block(
+                        Block::default()
+                            .title(UI_MESSAGE_FLOW_TITLE)
+                            .borders(Borders::ALL),
+                    );
+            rect.render_widget(hint, chunks[0]);
+        }
+    }
+    let link_queues_table = draw_link_queues_table(app);
+    rect.render_widget(link_queues_table, chunks[1]);
+}
+
+fn render_kv_browser<B>(rect: &mut Frame<B>, app: &mut UIApp, area: Rect)
+where
+    B: Backend,
+{
+    let (side_area, main_area) = split_side(app, area);
+    render_output(rect, app, side_area);
+    let num_rows = app.kv_comparison.as_ref().map_or(0, |c| c.rows.len());
+    app.kv_scroll = app.kv_scroll.min(num_rows.saturating_sub(1));
+    match &app.kv_comparison {
+        Some(comparison) if !comparison.nodes.is_empty() => {
+            let widths: Vec<Constraint> = std::iter::once(Constraint::Length(24))
+                .chain(
+                    comparison
+                        .nodes
+                        .iter()
+                        .map(|_| Constraint::Ratio(1, comparison.nodes.len() as u32)),
+                )
+                .collect();
+            let kv_browser = draw_kv_browser(app, comparison, &widths);
+            rect.render_widget(kv_browser, main_area);
+        }
+        _ => {
+            let hint = Paragraph::new("Scanning the databases of the nodes...")
+                .style(Style::default().fg(Color::Gray))
+                .block(
+                    Block::default()
+                        .title(UI_KV_BROWSER_TITLE)
+                        .borders(Borders::ALL),
+                );
+            rect.render_widget(hint, main_area);
+        }
+    }
+}
+
+fn render_canvas<B>(rect: &mut Frame<B>, app: &UIApp, canvas_area: Rect)
+where
+    B: Backend,
+{
     let canvas_node = Canvas::default()
-        .block(Block::default().title("Canvas").borders(Borders::ALL))
+        .block(
+            Block::default()
+                .title("Canvas")
+                .title(draw_quorum_status(app))
+                .borders(Borders::ALL),
+        )
         .marker(Marker::Braille)
-        .x_bounds([-90.0, 90.0])
-        .y_bounds([-60.0, 60.0])
+        .x_bounds(UI_CANVAS_X_BOUNDS)
+        .y_bounds(UI_CANVAS_Y_BOUNDS)
         .paint(|ctx| {
             let canvas_components = make_canvas(app);
             for node_rect in canvas_components.nodes.values() {
-                if node_rect.color == UI_LEADER_RECT_COLOR {
+                if node_rect.color != Color::Reset {
                     ctx.draw(node_rect);
                 }
             }
+            for group_rect in &canvas_components.group_rects {
+                ctx.draw(group_rect);
+            }
+            for highlight_rect in &canvas_components.highlight_rects {
+                ctx.draw(highlight_rect);
+            }
 
             for label in canvas_components.labels.values() {
                 ctx.print(label.x, label.y, label.span.clone());
@@ -114,31 +327,37 @@ where
     let canvas_line_lable = Canvas::default()
         .block(Block::default().title("Canvas").borders(Borders::ALL))
         .marker(Marker::Braille)
-        .x_bounds([-90.0, 90.0])
-        .y_bounds([-60.0, 60.0])
+        .x_bounds(UI_CANVAS_X_BOUNDS)
+        .y_bounds(UI_CANVAS_Y_BOUNDS)
         .paint(|ctx| {
             let canvas_components = make_canvas(app);
 
-            for line in canvas_components.connections.values() {
+            for line in &canvas_components.connections {
                 ctx.draw(line);
             }
+            for label in &canvas_components.link_labels {
+                ctx.print(label.x, label.y, label.span.clone());
+            }
             for label in canvas_components.labels.values() {
                 ctx.print(label.x, label.y, label.span.clone());
             }
         });
-    rect.render_widget(canvas_line_lable, body_chunks[1]);
-    rect.render_widget(canvas_node, body_chunks[1]);
-
-    // Input
-    let textarea = app.input_area.clone();
-    let input = draw_input(textarea);
-    rect.render_widget(input.widget(), chunks[4]);
+    rect.render_widget(canvas_line_lable, canvas_area);
+    rect.render_widget(canvas_node, canvas_area);
 }
 
 struct CanvasComponents {
     nodes: HashMap<u64, Rectangle>,
-    connections: HashMap<(u64, u64), canvas::Line>,
+    /// Links between the nodes, split into dashes for the cut ones.
+    connections: Vec<canvas::Line>,
     labels: HashMap<u64, Label<'static>>,
+    /// Frames around the nodes in the color of their partition group.
+    group_rects: Vec<Rectangle>,
+    /// Blinking frames around the nodes that changed recently.
+    highlight_rects: Vec<Rectangle>,
+    /// Number of messages sent over each link when replaying a trace, and the queues of the
+    /// throttled links.
+    link_labels: Vec<Label<'static>>,
 }
 
 struct Label<'a> {
@@ -151,12 +370,15 @@ fn make_canvas(app: &UIApp) -> CanvasComponents {
     if app.nodes.is_empty() {
         return CanvasComponents {
             nodes: HashMap::new(),
-            connections: HashMap::new(),
+            connections: vec![],
             labels: HashMap::new(),
+            group_rects: vec![],
+            highlight_rects: vec![],
+            link_labels: vec![],
         };
     }
     let network_status = &app.network_state;
-    let num_of_nodes = network_status.alive_nodes.len();
+    let num_of_nodes = app.nodes.len();
     let node_width = 15.0;
     let radius = 50.0; // Radius of the circle
     let center_x = -node_width / 2.0; // X-coordinate of the circle's center
@@ -164,15 +386,19 @@ fn make_canvas(app: &UIApp) -> CanvasComponents {
 
     let angle_step = 2.0 * PI / (num_of_nodes as f64); // Angle increment between each rectangle
     let mut nodes_with_rects = HashMap::new();
+    let is_crashed = |pid: &u64| !network_status.alive_nodes.contains(pid);
 
-    // Rectangles, but only shows the leader
-    for i in 0..num_of_nodes {
+    // Rectangles of all the configured nodes, so that they keep their place when some crash,
+    // but only shows the leader, the leader candidates and the crashed nodes
+    for (i, node) in app.nodes.iter().enumerate() {
         let angle = i as f64 * angle_step;
         let x = center_x + radius * angle.cos();
         let y = center_y + radius * angle.sin();
-        let node_id = network_status.alive_nodes[i];
+        let node_id = node.pid;
         let color = match network_status.max_round {
+            _ if is_crashed(&node_id) => UI_CRASHED_NODE_COLOR,
             Some(Round { leader: l, .. }) if node_id == l => UI_LEADER_RECT_COLOR,
+            _ if network_status.quorum.candidates.contains(&node_id) => UI_CANDIDATE_RECT_COLOR,
             _ => Color::Reset,
         };
         // let rect = Rectangle::new(Point::new(x, y), 1.0, 1.0); // Adjust the width and height as desired
@@ -186,38 +412,154 @@ fn make_canvas(app: &UIApp) -> CanvasComponents {
         nodes_with_rects.insert(node_id, rect);
     }
 
-    // Connections
-    let mut lines = HashMap::new();
-    for i in 0..network_status.alive_nodes.len() {
-        for j in i..network_status.alive_nodes.len() {
-            let node1 = network_status.alive_nodes.get(i).unwrap();
-            let node2 = network_status.alive_nodes.get(j).unwrap();
-            let current_rect = nodes_with_rects.get(node1).unwrap();
-            let next_rect = nodes_with_rects.get(node2).unwrap();
-
-            if network_status.partitions.get(&(*node1, *node2)).is_none() {
-                let line = canvas::Line {
-                    x1: current_rect.x + current_rect.width / 2.0,
-                    y1: current_rect.y + current_rect.height / 2.0,
-                    x2: next_rect.x + next_rect.width / 2.0,
-                    y2: next_rect.y + next_rect.height / 2.0,
-                    color: Color::White,
+    // Connections, dashed when cut or to a crashed node
+    let mut lines = vec![];
+    for (i, node1) in app.nodes.iter().enumerate() {
+        for node2 in &app.nodes[i + 1..] {
+            let (pid1, pid2) = (node1.pid, node2.pid);
+            let (rect1, rect2) = (&nodes_with_rects[&pid1], &nodes_with_rects[&pid2]);
+            let line = canvas::Line {
+                x1: rect1.x + rect1.width / 2.0,
+                y1: rect1.y + rect1.height / 2.0,
+                x2: rect2.x + rect2.width / 2.0,
+                y2: rect2.y + rect2.height / 2.0,
+                color: Color::White,
+            };
+            let (color, dashed) = match () {
+                _ if network_status.is_cut(pid1, pid2) => (UI_CUT_LINK_COLOR, true),
+                _ if is_crashed(&pid1) || is_crashed(&pid2) => (UI_CRASHED_NODE_COLOR, true),
+                _ => (Color::White, false),
+            };
+            let color = match is_highlighted(app.link_changes.get(&(pid1, pid2))) {
+                true => UI_CHANGE_HIGHLIGHT_COLOR,
+                false => color,
+            };
+            match dashed {
+                true => lines.extend(dashes(&line, color)),
+                false => lines.push(canvas::Line { color, ..line }),
+            }
+        }
+    }
+
+    // Partition groups
+    let mut group_rects = vec![];
+    for (group, color) in network_status
+        .partition_groups
+        .iter()
+        .zip(UI_PARTITION_GROUP_COLORS.iter().cycle())
+    {
+        for rect in group.iter().filter_map(|pid| nodes_with_rects.get(pid)) {
+            group_rects.push(Rectangle {
+                x: rect.x - 3.0,
+                y: rect.y - 3.0,
+                width: rect.width + 6.0,
+                height: rect.height + 6.0,
+                color: *color,
+            });
+        }
+    }
+
+    // Traffic over the links
+    let mut link_labels = vec![];
+    if let Some(replay) = &app.replay {
+        for (i, node1) in network_status.alive_nodes.iter().enumerate() {
+            for node2 in &network_status.alive_nodes[i + 1..] {
+                let both_ways = [(*node1, *node2), (*node2, *node1)];
+                let (forwarded, dropped) = both_ways
+                    .iter()
+                    .filter_map(|link| replay.traffic.get(link))
+                    .fold((0, 0), |(f, d), t| (f + t.forwarded, d + t.dropped));
+                if forwarded + dropped == 0 {
+                    continue;
+                }
+                let (rect1, rect2) = (&nodes_with_rects[node1], &nodes_with_rects[node2]);
+                let (text, color) = match dropped {
+                    0 => (format!("{forwarded}/s"), Color::Gray),
+                    _ => (
+                        format!("{forwarded}/s ({dropped} dropped)"),
+                        Color::LightRed,
+                    ),
                 };
-                lines.insert((i as u64, j as u64), line);
+                link_labels.push(Label {
+                    x: (rect1.x + rect2.x + rect1.width) / 2.0,
+                    y: (rect1.y + rect2.y + rect1.height) / 2.0,
+                    span: Span::styled(text, Style::default().fg(color)),
+                });
             }
         }
     }
 
+    // Throttled links, labelled above the middle of the link from the lower pid and below it
+    // from the higher one
+    for queue in &app.link_queues {
+        let (Some(from), Some(to)) = (
+            nodes_with_rects.get(&queue.from),
+            nodes_with_rects.get(&queue.to),
+        ) else {
+            continue;
+        };
+        let color = match queue.messages {
+            0 => Color::Gray,
+            _ => Color::Yellow,
+        };
+        let offset = match queue.from < queue.to {
+            true => 5.0,
+            false => -5.0,
+        };
+        link_labels.push(Label {
+            x: (from.x + to.x + from.width) / 2.0,
+            y: (from.y + to.y + from.height) / 2.0 + offset,
+            span: Span::styled(
+                format!(
+                    "{}->{} {} q={} ({:.0}KB)",
+                    queue.from,
+                    queue.to,
+                    format_bandwidth(queue.bandwidth),
+                    queue.messages,
+                    queue.bytes as f64 / 1e3
+                ),
+                Style::default().fg(color),
+            ),
+        });
+    }
+
+    // Nodes that changed recently
+    let highlight_rects = nodes_with_rects
+        .iter()
+        .filter(|(node_id, _)| is_highlighted(app.node_changes.get(node_id)))
+        .map(|(_, rect)| Rectangle {
+            x: rect.x - 1.5,
+            y: rect.y - 1.5,
+            width: rect.width + 3.0,
+            height: rect.height + 3.0,
+            color: UI_CHANGE_HIGHLIGHT_COLOR,
+        })
+        .collect();
+
     // Labels
     let mut labels = HashMap::new();
     for (node_id, rect) in &nodes_with_rects {
         let node = app.nodes.iter().find(|node| node.pid == *node_id).unwrap();
+        let (state, style) = match network_status.freezes.get(node_id) {
+            _ if is_crashed(node_id) => (
+                "(crashed) ".to_string(),
+                Style::default().fg(Color::Gray).bg(UI_CRASHED_NODE_COLOR),
+            ),
+            Some(freeze) => (
+                format!("({freeze}) "),
+                Style::default().fg(Color::White).bold().bg(node.color),
+            ),
+            None => (
+                String::new(),
+                Style::default().fg(Color::White).bold().bg(node.color),
+            ),
+        };
         let label = Label {
             x: rect.x + rect.width / 4.0,
             y: rect.y + rect.width / 6.0,
             span: Span::styled(
-                " Node".to_string() + &*node_id.to_string() + " ",
-                Style::default().fg(Color::White).bold().bg(node.color),
+                " Node".to_string() + &*node_id.to_string() + " " + &state,
+                style,
             ),
         };
         labels.insert(*node_id, label);
@@ -227,18 +569,157 @@ fn make_canvas(app: &UIApp) -> CanvasComponents {
         nodes: nodes_with_rects,
         connections: lines,
         labels,
+        group_rects,
+        highlight_rects,
+        link_labels,
+    }
+}
+
+/// What a click on the canvas points at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CanvasTarget {
+    Node(u64),
+    /// Link between two nodes, the lower pid first.
+    Link(u64, u64),
+}
+
+/// Finds the node, or else the closest link, drawn at a cell of the terminal.
+pub fn canvas_target(app: &UIApp, column: u16, row: u16) -> Option<CanvasTarget> {
+    let area = app.canvas_area.inner(&Margin {
+        vertical: 1,
+        horizontal: 1,
+    });
+    if !(area.left()..area.right()).contains(&column) || !(area.top()..area.bottom()).contains(&row)
+    {
+        return None;
+    }
+    let ([left, right], [bottom, top]) = (UI_CANVAS_X_BOUNDS, UI_CANVAS_Y_BOUNDS);
+    let cell_width = (right - left) / area.width as f64;
+    let cell_height = (top - bottom) / area.height as f64;
+    let x = left + ((column - area.x) as f64 + 0.5) * cell_width;
+    let y = top - ((row - area.y) as f64 + 0.5) * cell_height;
+
+    let nodes = make_canvas(app).nodes;
+    let clicked_node = nodes.iter().find(|(_, rect)| {
+        (rect.x..=rect.x + rect.width).contains(&x) && (rect.y..=rect.y + rect.height).contains(&y)
+    });
+    if let Some((pid, _)) = clicked_node {
+        return Some(CanvasTarget::Node(*pid));
     }
+    let center = |pid: u64| {
+        let rect = &nodes[&pid];
+        (rect.x + rect.width / 2.0, rect.y + rect.height / 2.0)
+    };
+    let mut closest: Option<(f64, CanvasTarget)> = None;
+    for (i, node1) in app.nodes.iter().enumerate() {
+        for node2 in &app.nodes[i + 1..] {
+            let (pid1, pid2) = (node1.pid.min(node2.pid), node1.pid.max(node2.pid));
+            let distance = distance_to_segment((x, y), center(pid1), center(pid2));
+            if !matches!(closest, Some((d, _)) if d <= distance) {
+                closest = Some((distance, CanvasTarget::Link(pid1, pid2)));
+            }
+        }
+    }
+    closest
+        .filter(|(distance, _)| *distance <= cell_width.max(cell_height))
+        .map(|(_, target)| target)
+}
+
+fn distance_to_segment((x, y): (f64, f64), (x1, y1): (f64, f64), (x2, y2): (f64, f64)) -> f64 {
+    let (dx, dy) = (x2 - x1, y2 - y1);
+    let t = (((x - x1) * dx + (y - y1) * dy) / (dx * dx + dy * dy)).clamp(0.0, 1.0);
+    ((x - x1 - t * dx).powi(2) + (y - y1 - t * dy).powi(2)).sqrt()
+}
+
+/// Whether something that changed at `changed_at` is in the on phase of its blinking.
+fn is_highlighted(changed_at: Option<&Instant>) -> bool {
+    let Some(changed_at) = changed_at else {
+        return false;
+    };
+    let elapsed = changed_at.elapsed();
+    let blinks = elapsed.as_millis() / UI_CHANGE_BLINK_RATE.as_millis();
+    elapsed < UI_CHANGE_HIGHLIGHT_TIME && blinks % 2 != 1
+}
+
+/// Splits a line into dashes.
+fn dashes(line: &canvas::Line, color: Color) -> Vec<canvas::Line> {
+    let (dash, gap) = (4.0, 3.0);
+    let (dx, dy) = (line.x2 - line.x1, line.y2 - line.y1);
+    let length = (dx * dx + dy * dy).sqrt();
+    let point = |dist: f64| (line.x1 + dx * dist / length, line.y1 + dy * dist / length);
+    let mut dashes = vec![];
+    let mut start = 0.0;
+    while start < length {
+        let (x1, y1) = point(start);
+        let (x2, y2) = point((start + dash).min(length));
+        dashes.push(canvas::Line {
+            x1,
+            y1,
+            x2,
+            y2,
+            color,
+        });
+        start += dash + gap;
+    }
+    dashes
 }
 
-fn draw_title<'a>(_app: &UIApp) -> Paragraph<'a> {
-    Paragraph::new(UI_TITLE)
-        .style(Style::default().fg(Color::LightCyan))
-        .alignment(Alignment::Center)
+/// Status line with the quorums the nodes can form, shown below the canvas.
+fn draw_quorum_status<'a>(app: &UIApp) -> Title<'a> {
+    let quorum = &app.network_state.quorum;
+    let line = match app.network_state.nodes.is_empty() {
+        true => Line::default(),
+        false => {
+            let color = match quorum.can_decide() {
+                true => Color::Green,
+                false => Color::Red,
+            };
+            Line::from(Span::styled(quorum.to_string(), Style::default().fg(color)))
+        }
+    };
+    Title::from(line).position(Position::Bottom)
+}
+
+fn draw_tabs<'a>(app: &UIApp) -> Tabs<'a> {
+    let mut spans = vec![Span::styled(
+        UI_TITLE,
+        Style::default().fg(Color::LightCyan),
+    )];
+    if let Some(replay) = &app.replay {
+        let state = match replay.paused {
+            true => "paused",
+            false => "playing",
+        };
+        spans.push(Span::styled(
+            format!(
+                " | Replay {state} {:.1}s / {:.1}s at {}x",
+                replay.position.as_secs_f64(),
+                replay.duration.as_secs_f64(),
+                replay.speed
+            ),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    let titles = Tab::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, tab)| Line::from(format!("F{} {tab}", i + 1)))
+        .collect();
+    Tabs::new(titles)
+        .select(app.layout.tab.index())
+        .style(Style::default().fg(Color::Gray))
+        .highlight_style(Style::default().fg(Color::Yellow).bold())
         .block(
             Block::default()
                 .borders(Borders::ALL)
                 .style(Style::default().fg(Color::White))
-                .border_type(BorderType::Plain),
+                .border_type(BorderType::Plain)
+                .title(Title::from(Line::from(spans)).alignment(Alignment::Center))
+                .title(
+                    Title::from(Span::styled(UI_TABS_HINT, Style::default().fg(Color::Gray)))
+                        .position(Position::Bottom)
+                        .alignment(Alignment::Right),
+                ),
         )
 }
 
@@ -267,6 +748,75 @@ fn draw_chart<'a>(app: &UIApp, data: &'a Vec<(&'a str, u64)>) -> BarChart<'a> {
         .label_style(Style::default().fg(Color::Yellow))
         .bar_style(Style::default().fg(leader.color))
 }
+fn draw_latency_chart<'a>(app: &'a UIApp) -> Chart<'a> {
+    let (x_min, x_max) = match (app.latency_p99_data.first(), app.latency_p99_data.last()) {
+        (Some((first, _)), Some((last, _))) => (*first, last.max(first + 1.0)),
+        _ => (0.0, 1.0),
+    };
+    let y_max = app
+        .latency_p99_data
+        .iter()
+        .map(|(_, latency)| *latency)
+        .fold(1.0, f64::max);
+    let datasets = vec![
+        Dataset::default()
+            .name("p50")
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Cyan))
+            .data(&app.latency_p50_data),
+        Dataset::default()
+            .name("p99")
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::LightRed))
+            .data(&app.latency_p99_data),
+    ];
+    Chart::new(datasets)
+        .block(
+            Block::default()
+                .title(UI_LATENCY_CHART_TITLE)
+                .borders(Borders::ALL),
+        )
+        .x_axis(Axis::default().bounds([x_min, x_max]))
+        .y_axis(
+            Axis::default()
+                .bounds([0.0, y_max])
+                .labels(vec![Span::raw("0"), Span::raw(format!("{:.0}", y_max))])
+                .style(Style::default().fg(Color::Gray)),
+        )
+}
+
+fn draw_latency_stats<'a>(app: &UIApp) -> Paragraph<'a> {
+    let latency = &app.latency;
+    let line = |name: &str, value: String| {
+        Line::from(vec![
+            Span::styled(format!("{name:<6}"), Style::default().fg(Color::Yellow)),
+            Span::raw(value),
+        ])
+    };
+    let as_ms = |latency: std::time::Duration| format!("{:.2} ms", latency.as_secs_f64() * 1000.0);
+    let lines = if latency.count == 0 {
+        vec![Line::from(Span::styled(
+            "N/A",
+            Style::default().fg(Color::Gray),
+        ))]
+    } else {
+        vec![
+            line("p50", as_ms(latency.p50)),
+            line("p95", as_ms(latency.p95)),
+            line("p99", as_ms(latency.p99)),
+            line("max", as_ms(latency.max)),
+            line("count", latency.count.to_string()),
+        ]
+    };
+    Paragraph::new(lines).block(
+        Block::default()
+            .title(UI_LATENCY_STATS_TITLE)
+            .borders(Borders::ALL),
+    )
+}
+
 fn draw_progress_bar<'a>(app: &UIApp) -> Gauge<'a> {
     let (progress, total, label) = {
         let (prog, tot) = (app.progress.finished, app.progress.total);
@@ -299,13 +849,304 @@ fn draw_progress_bar<'a>(app: &UIApp) -> Gauge<'a> {
         .label(label)
 }
 
-fn draw_input(mut textarea: TextArea) -> TextArea {
+fn draw_replication_table<'a>(app: &UIApp) -> Table<'a> {
+    let node_status = &app.network_state.node_status;
+    // Progress and lag are both measured against the leader's decided index, or the most
+    // advanced node's if the leader hasn't reported its status yet.
+    let leader_decided_idx = app
+        .network_state
+        .max_round
+        .and_then(|round| node_status.get(&round.leader))
+        .map(|status| status.decided_idx)
+        .unwrap_or_else(|| {
+            node_status
+                .values()
+                .map(|s| s.decided_idx)
+                .max()
+                .unwrap_or(0)
+        });
+    let rows = app.nodes.iter().map(|node| {
+        let node_label = Cell::from(format!("Node {}", node.pid))
+            .style(Style::default().fg(Color::White).bg(node.color).bold());
+        match node_status.get(&node.pid) {
+            Some(status) => {
+                let progress = if leader_decided_idx == 0 {
+                    1.0
+                } else {
+                    (status.decided_idx as f64 / leader_decided_idx as f64).min(1.0)
+                };
+                let lag = leader_decided_idx.saturating_sub(status.decided_idx);
+                let mut filled = (progress * UI_REPLICATION_BAR_WIDTH as f64).round() as usize;
+                if lag > 0 {
+                    // Don't round a lagging node up to a full bar
+                    filled = filled.min(UI_REPLICATION_BAR_WIDTH - 1);
+                }
+                let bar = format!(
+                    "{}{}",
+                    "█".repeat(filled),
+                    "░".repeat(UI_REPLICATION_BAR_WIDTH - filled)
+                );
+                let lag_color = if lag == 0 { Color::Green } else { Color::Red };
+                Row::new(vec![
+                    node_label,
+                    Cell::from(format!("{:?}", status.role)),
+                    Cell::from(status.accepted_idx.to_string()),
+                    Cell::from(status.decided_idx.to_string()),
+                    Cell::from(lag.to_string()).style(Style::default().fg(lag_color)),
+                    Cell::from(bar).style(Style::default().fg(node.color)),
+                ])
+            }
+            None => Row::new(vec![
+                node_label,
+                Cell::from("N/A").style(Style::default().fg(Color::Gray)),
+            ]),
+        }
+    });
+    Table::new(rows.collect::<Vec<_>>())
+        .header(
+            Row::new(vec!["", "Role", "Accepted", "Decided", "Lag", "Progress"])
+                .style(Style::default().fg(Color::Yellow).bold()),
+        )
+        .block(
+            Block::default()
+                .title(UI_REPLICATION_TITLE)
+                .borders(Borders::ALL),
+        )
+        .widths(&[
+            Constraint::Length(8),
+            Constraint::Length(9),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(UI_REPLICATION_BAR_WIDTH as u16),
+        ])
+}
+
+fn draw_node_status_table<'a>(app: &UIApp) -> Table<'a> {
+    let network_state = &app.network_state;
+    let rows = app.nodes.iter().map(|node| {
+        let node_label = Cell::from(format!("Node {}", node.pid))
+            .style(Style::default().fg(Color::White).bg(node.color).bold());
+        let state = match network_state.freezes.get(&node.pid) {
+            _ if !network_state.alive_nodes.contains(&node.pid) => {
+                Cell::from("crashed").style(Style::default().fg(Color::Red))
+            }
+            Some(freeze) => {
+                Cell::from(freeze.to_string()).style(Style::default().fg(Color::Yellow))
+            }
+            None => Cell::from("up").style(Style::default().fg(Color::Green)),
+        };
+        match network_state.node_status.get(&node.pid) {
+            Some(status) => {
+                let promise = match status.promise {
+                    Some(round) => format!("#{} by Node{}", round.round_num, round.leader),
+                    None => "-".to_string(),
+                };
+                let peers = status
+                    .connected_peers
+                    .iter()
+                    .map(|pid| pid.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",");
+                Row::new(vec![
+                    node_label,
+                    state,
+                    Cell::from(promise),
+                    Cell::from(status.compacted_idx.to_string()),
+                    Cell::from(status.log_size.to_string()),
+                    Cell::from(peers),
+                ])
+            }
+            None => Row::new(vec![node_label, state]),
+        }
+    });
+    Table::new(rows.collect::<Vec<_>>())
+        .header(
+            Row::new(vec![
+                "",
+                "State",
+                "Promise",
+                "Compacted",
+                "Log size",
+                "Heartbeats from",
+            ])
+            .style(Style::default().fg(Color::Yellow).bold()),
+        )
+        .block(
+            Block::default()
+                .title(UI_NODE_STATUS_TITLE)
+                .borders(Borders::ALL),
+        )
+        .widths(&[
+            Constraint::Length(8),
+            Constraint::Length(16),
+            Constraint::Length(16),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Min(15),
+        ])
+}
+
+/// Message types sent over any link since the inspection started.
+fn message_flow_kinds(report: &MessageFlowReport) -> Vec<MessageKind> {
+    let mut kinds: Vec<MessageKind> = report
+        .links
+        .iter()
+        .flat_map(|link| link.totals.keys().copied())
+        .collect();
+    kinds.sort();
+    kinds.dedup();
+    kinds
+}
+
+fn draw_message_flow<'a>(
+    report: &MessageFlowReport,
+    kinds: &[MessageKind],
+    widths: &'a [Constraint],
+) -> Table<'a> {
+    let rate_cell = |rate: f64| {
+        let style = match rate > 0.0 {
+            true => Style::default(),
+            false => Style::default().fg(Color::DarkGray),
+        };
+        Cell::from(format!("{rate:.0}")).style(style)
+    };
+    let rows = report.links.iter().map(|link| {
+        let rates = kinds
+            .iter()
+            .map(|kind| rate_cell(link.rates.get(kind).copied().unwrap_or(0.0)));
+        let total: f64 = link.rates.values().sum();
+        let dropped = match link.dropped_rate > 0.0 {
+            true => Cell::from(format!("{:.0}", link.dropped_rate))
+                .style(Style::default().fg(Color::Red)),
+            false => rate_cell(0.0),
+        };
+        Row::new(
+            std::iter::once(Cell::from(format!("{}->{}", link.from, link.to)))
+                .chain(rates)
+                .chain([dropped, rate_cell(total)])
+                .collect::<Vec<_>>(),
+        )
+    });
+    let header = std::iter::once("Link")
+        .chain(kinds.iter().map(|kind| kind.short_name()))
+        .chain(["Dropped", "Total"]);
+    Table::new(rows.collect::<Vec<_>>())
+        .header(
+            Row::new(header.collect::<Vec<_>>()).style(Style::default().fg(Color::Yellow).bold()),
+        )
+        .block(
+            Block::default()
+                .title(format!("{UI_MESSAGE_FLOW_TITLE}, phase: {}", report.phase))
+                .borders(Borders::ALL),
+        )
+        .widths(widths)
+}
+
+/// Keys and values of each node, where the keys the nodes disagree on come first and the values
+/// that differ from the most common one are highlighted.
+fn draw_kv_browser<'a>(
+    app: &UIApp,
+    comparison: &KVComparison,
+    widths: &'a [Constraint],
+) -> Table<'a> {
+    let divergent = comparison.rows.iter().filter(|row| row.divergent).count();
+    let rows = comparison.rows.iter().skip(app.kv_scroll).map(|row| {
+        let most_common = row
+            .values
+            .iter()
+            .max_by_key(|value| row.values.iter().filter(|v| v == value).count())
+            .unwrap();
+        let key_style = match row.divergent {
+            true => Style::default().fg(Color::LightRed).bold(),
+            false => Style::default(),
+        };
+        let values = row.values.iter().map(|value| {
+            let style = match value == most_common {
+                true => Style::default(),
+                false => Style::default().fg(Color::White).bg(Color::Red),
+            };
+            match value {
+                Some(value) => Cell::from(value.clone()).style(style),
+                None => Cell::from("(missing)").style(style.fg(Color::Gray)),
+            }
+        });
+        Row::new(
+            std::iter::once(Cell::from(row.key.clone()).style(key_style))
+                .chain(values)
+                .collect::<Vec<_>>(),
+        )
+    });
+    let header = std::iter::once("Key".to_string()).chain(comparison.nodes.iter().map(|pid| {
+        match comparison.stale.contains(pid) {
+            true => format!("Node {pid} (stale)"),
+            false => format!("Node {pid}"),
+        }
+    }));
+    let (summary, color) = match divergent {
+        0 => ("all nodes agree".to_string(), Color::Green),
+        n => (format!("{n} divergent keys"), Color::Red),
+    };
+    let truncated = match comparison.truncated {
+        true => format!(" (first {KV_SCAN_MAX_KEYS})"),
+        false => String::new(),
+    };
+    let title = Line::from(vec![
+        Span::raw(format!(
+            "{UI_KV_BROWSER_TITLE} | {} keys{truncated}, ",
+            comparison.rows.len()
+        )),
+        Span::styled(summary, Style::default().fg(color)),
+    ]);
+    Table::new(rows.collect::<Vec<_>>())
+        .header(
+            Row::new(header.collect::<Vec<_>>()).style(Style::default().fg(Color::Yellow).bold()),
+        )
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .widths(widths)
+}
+
+fn draw_link_queues_table<'a>(app: &UIApp) -> Table<'a> {
+    let rows = app.link_queues.iter().map(|queue| {
+        let color = match queue.messages {
+            0 => Color::Gray,
+            _ => Color::Yellow,
+        };
+        Row::new(vec![
+            Cell::from(format!("{}->{}", queue.from, queue.to)),
+            Cell::from(format_bandwidth(queue.bandwidth)),
+            Cell::from(queue.messages.to_string()).style(Style::default().fg(color)),
+            Cell::from(format!("{:.1}KB", queue.bytes as f64 / 1e3))
+                .style(Style::default().fg(color)),
+        ])
+    });
+    Table::new(rows.collect::<Vec<_>>())
+        .header(
+            Row::new(vec!["Link", "Bandwidth", "Queued", "Queued bytes"])
+                .style(Style::default().fg(Color::Yellow).bold()),
+        )
+        .block(
+            Block::default()
+                .title(UI_LINK_QUEUES_TITLE)
+                .borders(Borders::ALL),
+        )
+        .widths(&[
+            Constraint::Length(6),
+            Constraint::Length(12),
+            Constraint::Length(8),
+            Constraint::Length(14),
+        ])
+}
+
+fn draw_input(mut textarea: TextArea, target_node: Option<u64>) -> TextArea {
     textarea.set_style(Style::default().fg(Color::LightGreen));
-    textarea.set_block(
-        Block::default()
-            .borders(Borders::ALL)
-            .title(UI_INPUT_AREA_TITLE),
-    );
+    let title = match target_node {
+        Some(pid) => format!(
+            "Send through node {pid}: get <key> or put <key> <value> (Enter to send, Esc to cancel)"
+        ),
+        None => UI_INPUT_AREA_TITLE.to_string(),
+    };
+    textarea.set_block(Block::default().borders(Borders::ALL).title(title));
 
     textarea
 }
@@ -335,6 +1176,73 @@ fn draw_output<'a>(app: &UIApp, block_height: i64, _block_width: i64) -> Paragra
     )
 }
 
+fn draw_timeline<'a>(app: &UIApp, block_height: i64) -> Paragraph<'a> {
+    let node_style = |pid: u64| {
+        let color = app
+            .nodes
+            .iter()
+            .find(|node| node.pid == pid)
+            .map_or(Color::Reset, |node| node.color);
+        Style::default().fg(Color::White).bg(color).bold()
+    };
+    let lines = app
+        .elections
+        .iter()
+        .map(|record| {
+            let mut spans = vec![
+                Span::styled(
+                    format!("[{}] ", record.time.format("%H:%M:%S%.3f")),
+                    Style::default().fg(Color::Gray),
+                ),
+                Span::styled(
+                    format!("#{} ", record.round.round_num),
+                    Style::default().fg(Color::Yellow),
+                ),
+            ];
+            if let Some(old_leader) = record.old_leader {
+                spans.push(Span::styled(
+                    format!(" Node{old_leader} "),
+                    node_style(old_leader),
+                ));
+                spans.push(Span::raw(" -> "));
+            }
+            spans.push(Span::styled(
+                format!(" Node{} ", record.round.leader),
+                node_style(record.round.leader),
+            ));
+            let unavailability = match record.unavailability {
+                Some(duration) => format!(" unavailable {:.3}s", duration.as_secs_f64()),
+                None => " waiting for decisions...".to_string(),
+            };
+            spans.push(Span::styled(
+                unavailability,
+                Style::default().fg(Color::LightRed),
+            ));
+            if !record.partitions.is_empty() {
+                let cut_links = record
+                    .partitions
+                    .iter()
+                    .map(|(a, b)| format!("{a}-{b}"))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                spans.push(Span::styled(
+                    format!(", cut: {cut_links}"),
+                    Style::default().fg(Color::Gray),
+                ));
+            }
+            Line::from(spans)
+        })
+        .collect::<Vec<_>>();
+    let num_lines = lines.len() as i64;
+    Paragraph::new(lines)
+        .scroll(((num_lines - block_height).max(0) as u16, 0))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(UI_TIMELINE_TITLE),
+        )
+}
+
 #[allow(dead_code)]
 // This is a temporary solution to the problem of long lines in the output area.
 fn reformat_output(logs: Vec<String>, block_width: u64) -> Vec<String> {
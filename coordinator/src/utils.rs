@@ -2,6 +2,55 @@ use ratatui::prelude::Color;
 use std::time::Duration;
 
 pub const PROPOSE_TICK_RATE: Duration = Duration::from_millis(1);
+/// Longest idle period for which the proposer saves up credit to catch up with its target rate.
+pub const PROPOSE_BURST_WINDOW: Duration = Duration::from_millis(10);
+/// Upper bound on commands sent per tick when the proposal rate is unlimited.
+pub const PROPOSE_MAX_PER_TICK: usize = 10000;
+pub const DEFAULT_PROPOSAL_TIMEOUT: Duration = Duration::from_secs(10);
+pub const PROPOSAL_TIMEOUT_CHECK_RATE: Duration = Duration::from_millis(100);
+/// Number of commands a running workload keeps queued for the proposer.
+pub const WORKLOAD_QUEUE_TARGET: usize = 1000;
+pub const DOWNTIME_CHECK_RATE: Duration = Duration::from_millis(100);
+/// How long the cluster must not decide anything with proposals pending to count as downtime.
+pub const DOWNTIME_THRESHOLD: Duration = Duration::from_millis(500);
+pub const LATENCY_REPORT_RATE: Duration = Duration::from_millis(100);
+/// Latencies above this are recorded as this value.
+pub const LATENCY_MAX_TRACKABLE: Duration = Duration::from_secs(60);
+/// Upper bound on the steps of the linearizability search for a single key.
+pub const CHECK_MAX_SEARCH_STEPS: usize = 10_000_000;
+/// Number of operations considered when minimizing a non-linearizable history.
+pub const CHECK_MAX_SHRINK_OPS: usize = 100;
+/// Number of operations after which the oldest completed ones are dropped from the history.
+pub const HISTORY_MAX_OPS: usize = 100_000;
+pub const DEFAULT_CHAOS_INTERVAL: Duration = Duration::from_secs(2);
+/// Bounds of the delays the nemesis adds to links.
+pub const CHAOS_MIN_LINK_DELAY: Duration = Duration::from_millis(10);
+pub const CHAOS_MAX_LINK_DELAY: Duration = Duration::from_millis(500);
+/// Traffic a throttled link lets through at once after being idle.
+pub const BANDWIDTH_BURST_WINDOW: Duration = Duration::from_millis(100);
+pub const LINK_QUEUE_REPORT_RATE: Duration = Duration::from_millis(250);
+pub const MESSAGE_FLOW_REPORT_RATE: Duration = Duration::from_secs(1);
+/// How often the databases of the nodes are scanned while the KV browser is shown.
+pub const KV_SCAN_RATE: Duration = Duration::from_secs(1);
+/// Keys read from each database in a scan.
+pub const KV_SCAN_MAX_KEYS: usize = 1000;
+pub const REPLAY_TICK_RATE: Duration = Duration::from_millis(100);
+/// Period over which the replay counts the messages sent over each link.
+pub const REPLAY_TRAFFIC_WINDOW: Duration = Duration::from_secs(1);
+pub const DEFAULT_DOWNTIME_REPORT_FILE: &str = "downtime_report.json";
+pub const DEFAULT_UI_LAYOUT_FILE: &str = "ui_layout.json";
+/// How often the benchmark samples throughput and latency.
+pub const BENCH_SAMPLE_RATE: Duration = Duration::from_secs(1);
+/// How long the benchmark waits for a leader to be elected before starting the workload.
+pub const BENCH_STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+/// Scenarios of the `scenario` command.
+pub const SCENARIOS: [&str; 4] = ["qloss", "constrained", "chained", "restore"];
+/// Number of nodes the scenarios are written for.
+pub const SCENARIO_NODES: usize = 5;
+/// How long the benchmark waits for outstanding proposals to be decided after the workload.
+pub const BENCH_DRAIN_TIME: Duration = Duration::from_secs(1);
+pub const DEFAULT_BENCH_DURATION: Duration = Duration::from_secs(60);
+pub const DEFAULT_BENCH_REPORT_FILE: &str = "bench_report.json";
 pub const CHANNEL_BUFFER_SIZE: usize = 100000;
 pub const UI_TITLE: &str = "The OmniPaxos Playground (press 'q' or 'esc' to exit)";
 pub const UI_INPUT_AREA_TITLE: &str = "Input (Enter to send)";
@@ -10,10 +59,50 @@ pub const UI_OUTPUT_AREA_TITLE: &str =
     "Output (Scroll with Up/Down, Quit with Ctrl-C, Help with help)";
 pub const UI_TICK_RATE: Duration = Duration::from_millis(100);
 pub const UI_MAX_DECIDED_BARS: usize = 200;
+pub const UI_LATENCY_CHART_TITLE: &str = "Latency (ms)";
+pub const UI_LATENCY_STATS_TITLE: &str = "Latency";
+pub const UI_MAX_LATENCY_POINTS: usize = 200;
+/// Number of failed proposals logged individually per report.
+pub const UI_MAX_FAILURE_LOGS: usize = 10;
 pub const UI_PROGRESS_BAR_TITLE: &str = "Progress (Finished/Total)";
+pub const UI_REPLICATION_TITLE: &str = "Replication (lag behind leader)";
+pub const UI_REPLICATION_BAR_WIDTH: usize = 30;
+pub const UI_MESSAGE_FLOW_TITLE: &str = "Message flow (msgs/s)";
+pub const UI_TIMELINE_TITLE: &str = "Leader Elections";
+pub const UI_NODE_STATUS_TITLE: &str = "Node status";
+pub const UI_LINK_QUEUES_TITLE: &str = "Throttled links";
+/// Rows the KV browser scrolls by on PageUp and PageDown.
+pub const UI_KV_SCROLL_STEP: usize = 10;
+pub const UI_KV_BROWSER_TITLE: &str = "Keys and values of each node (scroll with PageUp/PageDown)";
+pub const UI_TABS_HINT: &str = "Tab/F1-F5: switch view, Alt+Left/Right/Up/Down: resize panes";
+/// Smallest and largest width of the output column, in percent of the window.
+pub const UI_SIDE_WIDTH_RANGE: (u16, u16) = (10, 70);
+pub const UI_SIDE_WIDTH_STEP: i32 = 5;
+/// Smallest and largest height of the charts, in rows.
+pub const UI_CHART_HEIGHT_RANGE: (u16, u16) = (5, 30);
 pub const UI_BARCHART_WIDTH: u16 = 3;
 pub const UI_BARCHART_GAP: u16 = 1;
 pub const UI_LEADER_RECT_COLOR: Color = Color::Green;
+/// Color of the nodes that can be elected leader with the current links.
+pub const UI_CANDIDATE_RECT_COLOR: Color = Color::Gray;
+/// Canvas coordinates of the left and right, and bottom and top edges of the canvas.
+pub const UI_CANVAS_X_BOUNDS: [f64; 2] = [-90.0, 90.0];
+pub const UI_CANVAS_Y_BOUNDS: [f64; 2] = [-60.0, 60.0];
+/// Color of the crashed nodes and their links.
+pub const UI_CRASHED_NODE_COLOR: Color = Color::DarkGray;
+pub const UI_CUT_LINK_COLOR: Color = Color::Red;
+/// Nodes and links that changed recently blink in this color for `UI_CHANGE_HIGHLIGHT_TIME`.
+pub const UI_CHANGE_HIGHLIGHT_COLOR: Color = Color::LightYellow;
+pub const UI_CHANGE_HIGHLIGHT_TIME: Duration = Duration::from_secs(3);
+pub const UI_CHANGE_BLINK_RATE: Duration = Duration::from_millis(300);
+/// Colors of the frames around the nodes of each partition group.
+pub const UI_PARTITION_GROUP_COLORS: [Color; 5] = [
+    Color::Yellow,
+    Color::Cyan,
+    Color::Magenta,
+    Color::LightBlue,
+    Color::LightRed,
+];
 pub const ORANGE: Color = Color::Indexed(208);
 pub const PINK: Color = Color::Indexed(211);
 
@@ -27,3 +116,51 @@ pub(crate) const COLORS: [Color; 8] = [
     Color::Yellow,
     PINK,
 ];
+
+/// Returns the value following `flag` in the command line arguments, if any.
+pub fn get_arg_value(flag: &str) -> Option<String> {
+    let mut args = std::env::args().skip_while(|arg| arg != flag);
+    args.next().and_then(|_| args.next())
+}
+
+/// Parses a duration like `500ms`, `2s`, `1.5m` or `30` (seconds).
+pub fn parse_duration(s: &str) -> Option<Duration> {
+    let (value, unit_secs) = if let Some(ms) = s.strip_suffix("ms") {
+        (ms, 0.001)
+    } else if let Some(secs) = s.strip_suffix('s') {
+        (secs, 1.0)
+    } else if let Some(mins) = s.strip_suffix('m') {
+        (mins, 60.0)
+    } else {
+        (s, 1.0)
+    };
+    let value: f64 = value.parse().ok()?;
+    (value >= 0.0 && value.is_finite()).then(|| Duration::from_secs_f64(value * unit_secs))
+}
+
+/// Parses a bandwidth like `1MB/s`, `500KB/s` or `100B/s` into bytes per second.
+pub fn parse_bandwidth(s: &str) -> Option<u64> {
+    let s = s.strip_suffix("/s").unwrap_or(s).to_uppercase();
+    let (value, unit) = if let Some(gb) = s.strip_suffix("GB") {
+        (gb, 1e9)
+    } else if let Some(mb) = s.strip_suffix("MB") {
+        (mb, 1e6)
+    } else if let Some(kb) = s.strip_suffix("KB") {
+        (kb, 1e3)
+    } else {
+        (s.strip_suffix('B').unwrap_or(&s), 1.0)
+    };
+    let value: f64 = value.parse().ok()?;
+    let rate = (value * unit).round();
+    (rate >= 1.0 && rate.is_finite()).then_some(rate as u64)
+}
+
+/// Formats bytes per second like `1.5MB/s`.
+pub fn format_bandwidth(rate: u64) -> String {
+    match rate {
+        r if r >= 1_000_000_000 => format!("{:.1}GB/s", r as f64 / 1e9),
+        r if r >= 1_000_000 => format!("{:.1}MB/s", r as f64 / 1e6),
+        r if r >= 1_000 => format!("{:.1}KB/s", r as f64 / 1e3),
+        r => format!("{r}B/s"),
+    }
+}
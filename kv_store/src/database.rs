@@ -26,6 +26,21 @@ impl Database {
         }
     }
 
+    /// Returns the first `limit` keys with their values, in key order.
+    pub fn scan(&self, limit: usize) -> Vec<KeyValue> {
+        self.sled
+            .iter()
+            .take(limit)
+            .map(|entry| match entry {
+                Ok((key, value)) => KeyValue {
+                    key: String::from_utf8(key.as_ref().into()).unwrap(),
+                    value: String::from_utf8(value.as_ref().into()).unwrap(),
+                },
+                Err(e) => panic!("failed to scan: {}", e),
+            })
+            .collect()
+    }
+
     fn get(&self, key: &str) -> Option<String> {
         match self.sled.get(key.as_bytes()) {
             Ok(Some(value)) => {
@@ -1,6 +1,10 @@
 use omnipaxos::storage::{Entry, Snapshot};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Number of applied request ids remembered to skip duplicates, far more than a client retries
+/// at once.
+const APPLIED_REQUESTS_CAPACITY: usize = 100_000;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct KeyValue {
@@ -16,7 +20,39 @@ pub enum KVCommand {
     Get(String),
 }
 
-impl Entry for KVCommand {
+/// A command in the replicated log, with the id of the request it was proposed in. The id is
+/// sent back to the client when the command is decided.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogCommand {
+    pub request_id: Option<u64>,
+    pub cmd: KVCommand,
+}
+
+/// Ids of the most recently applied requests. A client may send a request again when it doesn't
+/// know whether it was decided, e.g. after a leader change, so the same request can be decided
+/// twice. Since all nodes apply the same log, they all skip the same duplicates.
+#[derive(Debug, Default)]
+pub struct AppliedRequests {
+    ids: HashSet<u64>,
+    order: VecDeque<u64>,
+}
+
+impl AppliedRequests {
+    /// Records that the request `id` is applied. Returns false if it already was.
+    pub fn insert(&mut self, id: u64) -> bool {
+        if !self.ids.insert(id) {
+            return false;
+        }
+        self.order.push_back(id);
+        if self.order.len() > APPLIED_REQUESTS_CAPACITY {
+            let oldest = self.order.pop_front().unwrap();
+            self.ids.remove(&oldest);
+        }
+        true
+    }
+}
+
+impl Entry for LogCommand {
     type Snapshot = KVSnapshot;
 }
 
@@ -26,12 +62,12 @@ pub struct KVSnapshot {
     deleted_keys: Vec<String>,
 }
 
-impl Snapshot<KVCommand> for KVSnapshot {
-    fn create(entries: &[KVCommand]) -> Self {
+impl Snapshot<LogCommand> for KVSnapshot {
+    fn create(entries: &[LogCommand]) -> Self {
         let mut snapshotted = HashMap::new();
         let mut deleted_keys: Vec<String> = Vec::new();
         for e in entries {
-            match e {
+            match &e.cmd {
                 KVCommand::Put(KeyValue { key, value }) => {
                     snapshotted.insert(key.clone(), value.clone());
                 }
@@ -0,0 +1,25 @@
+use slog::{o, Drain, Logger};
+use std::fs::OpenOptions;
+
+/// Creates the logger used when running without the OmniPaxosUI dashboard.
+/// Logs to the given file if provided, otherwise to stdout.
+pub fn headless_logger(log_file: Option<&str>) -> Logger {
+    let drain = match log_file {
+        Some(path) => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .expect("failed to open log file");
+            let decorator = slog_term::PlainDecorator::new(file);
+            let drain = slog_term::FullFormat::new(decorator).build().fuse();
+            slog_async::Async::new(drain).build().fuse()
+        }
+        None => {
+            let decorator = slog_term::PlainDecorator::new(std::io::stdout());
+            let drain = slog_term::FullFormat::new(decorator).build().fuse();
+            slog_async::Async::new(drain).build().fuse()
+        }
+    };
+    Logger::root(drain, o!("pid" => *crate::PID))
+}
@@ -1,17 +1,26 @@
-use crate::kv::KVCommand;
+use crate::kv::{AppliedRequests, LogCommand};
 use crate::server::Server;
 use omnipaxos::{util::FlexibleQuorum, *};
 use omnipaxos_storage::memory_storage::MemoryStorage;
 use omnipaxos_ui::OmniPaxosUI;
 use std::env;
+use tracing::{info_span, Instrument};
 
 #[macro_use]
 extern crate lazy_static;
 
 mod database;
 mod kv;
+mod logger;
 mod network;
 mod server;
+mod telemetry;
+
+/// Returns the value following `flag` in the command line arguments, if any.
+fn get_arg_value(flag: &str) -> Option<String> {
+    let mut args = env::args().skip_while(|arg| arg != flag);
+    args.next().and_then(|_| args.next())
+}
 
 lazy_static! {
     pub static ref NODES: Vec<u64> = if let Ok(var) = env::var("NODES") {
@@ -41,17 +50,29 @@ lazy_static! {
     } else {
         None
     };
+    /// Run without the OmniPaxosUI dashboard, e.g. in CI or as a background process.
+    pub static ref HEADLESS: bool = env::args().any(|arg| arg == "--headless");
+    /// Optional file for the headless logger. Logs to stdout if not set.
+    pub static ref LOG_FILE: Option<String> = get_arg_value("--log-file");
+    /// Optional file to write structured traces to as JSON lines.
+    pub static ref TRACE_FILE: Option<String> = get_arg_value("--trace-file");
 }
 
-type OmniPaxosKV = OmniPaxos<KVCommand, MemoryStorage<KVCommand>>;
+type OmniPaxosKV = OmniPaxos<LogCommand, MemoryStorage<LogCommand>>;
 
 #[tokio::main]
 async fn main() {
     let pid = *PID;
+    let _trace_guard = telemetry::init(*HEADLESS, TRACE_FILE.as_deref());
+    let logger = if *HEADLESS {
+        logger::headless_logger(LOG_FILE.as_deref())
+    } else {
+        OmniPaxosUI::logger()
+    };
     let server_config = ServerConfig {
         pid,
         election_tick_timeout: 5,
-        custom_logger: Some(OmniPaxosUI::logger()),
+        custom_logger: Some(logger),
         ..Default::default()
     };
     let cluster_config = ClusterConfig {
@@ -63,8 +84,13 @@ async fn main() {
         server_config,
         cluster_config,
     };
-    let mut omni_paxos_ui = OmniPaxosUI::with(op_config.clone().into());
-    omni_paxos_ui.start();
+    let omni_paxos_ui = if *HEADLESS {
+        None
+    } else {
+        let mut omni_paxos_ui = OmniPaxosUI::with(op_config.clone().into());
+        omni_paxos_ui.start();
+        Some(omni_paxos_ui)
+    };
     let omni_paxos = op_config
         .build(MemoryStorage::default())
         .expect("failed to build OmniPaxos");
@@ -76,6 +102,7 @@ async fn main() {
         database: database::Database::new(format!("db_{}", *PID).as_str()),
         last_decided_idx: 0,
         last_sent_leader: None,
+        applied_requests: AppliedRequests::default(),
     };
-    server.run().await;
+    server.run().instrument(info_span!("node", pid)).await;
 }
@@ -9,13 +9,20 @@ use tokio::{
     sync::Mutex,
 };
 
-use crate::{kv::KVCommand, server::APIResponse, NODES, PID as MY_PID};
+use crate::{
+    kv::{KVCommand, LogCommand},
+    server::APIResponse,
+    NODES, PID as MY_PID,
+};
 pub(crate) const CLIENT_PID: u64 = 0;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) enum Message {
-    OmniPaxosMsg(OPMessage<KVCommand>),
-    APIRequest(KVCommand),
+    OmniPaxosMsg(OPMessage<LogCommand>),
+    /// A command with the id the client gave the request, if any.
+    APIRequest(KVCommand, Option<u64>),
+    /// Asks for the first keys of the database, up to the given number, with their values.
+    ScanRequest(usize),
     APIResponse(APIResponse, u64),
 }
 
@@ -1,5 +1,5 @@
 use crate::database::Database;
-use crate::kv::KVCommand;
+use crate::kv::{AppliedRequests, KVCommand, KeyValue, LogCommand};
 use crate::network::CLIENT_PID;
 use crate::{
     network::{Message, Network},
@@ -7,12 +7,15 @@ use crate::{
 };
 use omnipaxos::ballot_leader_election::Ballot;
 use omnipaxos::util::LogEntry;
+use omnipaxos::utils::ui::OmniPaxosStates;
 use omnipaxos_ui::OmniPaxosUI;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use tokio::time;
+use tracing::{debug, info, info_span};
 
 const SNAPSHOT_IDX: u64 = 100000;
+const STATUS_INTERVAL: Duration = Duration::from_millis(500);
 
 #[derive(Clone, Copy, Eq, Debug, Ord, PartialOrd, PartialEq, Serialize, Deserialize)]
 pub struct Round {
@@ -29,22 +32,48 @@ impl From<Ballot> for Round {
     }
 }
 
+#[derive(Clone, Copy, Eq, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Role {
+    Leader,
+    Follower,
+}
+
+/// Snapshot of a node's replication state, periodically reported to the network actor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeStatus {
+    pub promise: Option<Round>,
+    pub accepted_idx: u64,
+    pub decided_idx: u64,
+    pub compacted_idx: u64,
+    pub role: Role,
+    /// Peers this node has received heartbeats from in the last heartbeat round.
+    pub connected_peers: Vec<u64>,
+    /// Number of entries in the log that are not compacted.
+    pub log_size: u64,
+}
+
 /// Same as in network actor
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum APIResponse {
     Decided(u64),
-    Get(String, Option<String>),
+    /// Ids of the requests whose commands were just decided.
+    DecidedRequests(Vec<u64>),
+    /// The value of a key, with the id of the request that read it.
+    Get(String, Option<String>, Option<u64>),
     NewRound(Option<Round>),
+    Status(NodeStatus),
+    Scan(Vec<KeyValue>),
 }
 
 pub struct Server {
     pub pid: u64,
-    pub omni_paxos_ui: OmniPaxosUI,
+    pub omni_paxos_ui: Option<OmniPaxosUI>,
     pub omni_paxos: OmniPaxosKV,
     pub network: Network,
     pub database: Database,
     pub last_decided_idx: u64,
     pub last_sent_leader: Option<Ballot>,
+    pub applied_requests: AppliedRequests,
 }
 
 impl Server {
@@ -52,16 +81,34 @@ impl Server {
         let messages = self.network.get_received().await;
         for msg in messages {
             match msg {
-                Message::APIRequest(kv_cmd) => match kv_cmd {
+                Message::APIRequest(kv_cmd, request_id) => match kv_cmd {
                     KVCommand::Get(key) => {
                         let value = self.database.handle_command(KVCommand::Get(key.clone()));
-                        let msg = Message::APIResponse(APIResponse::Get(key, value), self.pid);
+                        let msg = Message::APIResponse(
+                            APIResponse::Get(key, value, request_id),
+                            self.pid,
+                        );
                         self.network.send(CLIENT_PID, msg).await;
                     }
                     cmd => {
-                        self.omni_paxos.append(cmd).unwrap();
+                        let _span = info_span!(
+                            "proposal",
+                            pid = self.pid,
+                            ballot = self.omni_paxos.get_promise().n,
+                            decided_idx = self.omni_paxos.get_decided_idx()
+                        )
+                        .entered();
+                        debug!(?cmd, request_id, "appending proposal");
+                        self.omni_paxos
+                            .append(LogCommand { request_id, cmd })
+                            .unwrap();
                     }
                 },
+                Message::ScanRequest(limit) => {
+                    let entries = self.database.scan(limit);
+                    let msg = Message::APIResponse(APIResponse::Scan(entries), self.pid);
+                    self.network.send(CLIENT_PID, msg).await;
+                }
                 Message::OmniPaxosMsg(msg) => {
                     self.omni_paxos.handle_incoming(msg);
                 }
@@ -87,6 +134,27 @@ impl Server {
                 .omni_paxos
                 .read_decided_suffix(self.last_decided_idx)
                 .unwrap();
+            {
+                let _span = info_span!(
+                    "decide",
+                    pid = self.pid,
+                    ballot = self.omni_paxos.get_promise().n,
+                    decided_idx = new_decided_idx
+                )
+                .entered();
+                info!(
+                    from_idx = self.last_decided_idx,
+                    entries = decided_entries.len(),
+                    "entries decided"
+                );
+            }
+            let request_ids: Vec<u64> = decided_entries
+                .iter()
+                .filter_map(|entry| match entry {
+                    LogEntry::Decided(entry) => entry.request_id,
+                    _ => None,
+                })
+                .collect();
             self.update_database(decided_entries);
             if new_decided_idx % SNAPSHOT_IDX == 0
                 || new_decided_idx - self.last_decided_idx > SNAPSHOT_IDX
@@ -97,6 +165,10 @@ impl Server {
             }
             self.last_decided_idx = new_decided_idx;
             /*** reply client ***/
+            if !request_ids.is_empty() {
+                let msg = Message::APIResponse(APIResponse::DecidedRequests(request_ids), self.pid);
+                self.network.send(CLIENT_PID, msg).await;
+            }
             let msg = Message::APIResponse(APIResponse::Decided(new_decided_idx), self.pid);
             self.network.send(CLIENT_PID, msg).await
         }
@@ -111,6 +183,16 @@ impl Server {
             Some(b)
         };
         if self.last_sent_leader != new_ballot {
+            // the default ballot means there is no leader to report
+            if new_ballot.is_some() {
+                info!(
+                    pid = self.pid,
+                    ballot = b.n,
+                    leader = b.pid,
+                    decided_idx = self.omni_paxos.get_decided_idx(),
+                    "leader changed"
+                );
+            }
             self.last_sent_leader = new_ballot;
             let msg = Message::APIResponse(
                 APIResponse::NewRound(new_ballot.map(|b| b.into())),
@@ -120,11 +202,55 @@ impl Server {
         }
     }
 
-    fn update_database(&self, decided_entries: Vec<LogEntry<KVCommand>>) {
+    async fn send_status(&mut self, ui_states: &OmniPaxosStates) {
+        let decided_idx = self.omni_paxos.get_decided_idx();
+        let compacted_idx = self.omni_paxos.get_compacted_idx();
+        let undecided_entries = self
+            .omni_paxos
+            .read_entries(decided_idx..)
+            .map_or(0, |entries| entries.len() as u64);
+        let accepted_idx = decided_idx + undecided_entries;
+        // `current_ballot` of the UI states is the ballot of BLE, not the promise of SequencePaxos
+        let promise = match self.omni_paxos.get_promise() {
+            b if b == Ballot::default() => None,
+            b => Some(b.into()),
+        };
+        let role = if ui_states.current_leader == Some(self.pid) {
+            Role::Leader
+        } else {
+            Role::Follower
+        };
+        let connected_peers = ui_states
+            .cluster_state
+            .heartbeats
+            .iter()
+            .map(|hb| hb.ballot.pid)
+            .filter(|pid| *pid != self.pid)
+            .collect();
+        let status = NodeStatus {
+            promise,
+            accepted_idx,
+            decided_idx,
+            compacted_idx,
+            role,
+            connected_peers,
+            log_size: accepted_idx - compacted_idx,
+        };
+        let msg = Message::APIResponse(APIResponse::Status(status), self.pid);
+        self.network.send(CLIENT_PID, msg).await;
+    }
+
+    fn update_database(&mut self, decided_entries: Vec<LogEntry<LogCommand>>) {
         for entry in decided_entries {
             match entry {
-                LogEntry::Decided(cmd) => {
-                    self.database.handle_command(cmd);
+                LogEntry::Decided(entry) => {
+                    if let Some(id) = entry.request_id {
+                        if !self.applied_requests.insert(id) {
+                            debug!(request_id = id, "skipping duplicate request");
+                            continue;
+                        }
+                    }
+                    self.database.handle_command(entry.cmd);
                 }
                 _ => {}
             }
@@ -134,6 +260,7 @@ impl Server {
     pub(crate) async fn run(&mut self) {
         let mut msg_interval = time::interval(Duration::from_millis(1));
         let mut tick_interval = time::interval(Duration::from_millis(100));
+        let mut status_interval = time::interval(STATUS_INTERVAL);
         loop {
             tokio::select! {
                 biased;
@@ -145,7 +272,13 @@ impl Server {
                 },
                 _ = tick_interval.tick() => {
                     self.omni_paxos.tick();
-                    self.omni_paxos_ui.tick(self.omni_paxos.get_ui_states());
+                    if let Some(omni_paxos_ui) = self.omni_paxos_ui.as_mut() {
+                        omni_paxos_ui.tick(self.omni_paxos.get_ui_states());
+                    }
+                },
+                _ = status_interval.tick() => {
+                    let ui_states = self.omni_paxos.get_ui_states();
+                    self.send_status(&ui_states).await;
                 },
                 else => (),
             }
@@ -0,0 +1,32 @@
+use std::fs::OpenOptions;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::prelude::*;
+
+/// Sets up structured tracing for the node. Events go to stdout when running headless, and
+/// additionally as JSON lines to the trace file if one is given, so that the traces of all nodes
+/// can be correlated after a run. The returned guard must be kept alive for the file to flush.
+pub fn init(headless: bool, trace_file: Option<&str>) -> Option<WorkerGuard> {
+    let stdout_layer = headless.then(|| tracing_subscriber::fmt::layer().compact());
+    let (file_layer, guard) = match trace_file {
+        Some(path) => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .expect("failed to open trace file");
+            let (writer, guard) = tracing_appender::non_blocking(file);
+            let layer = tracing_subscriber::fmt::layer()
+                .json()
+                .with_current_span(true)
+                .with_span_list(false)
+                .with_writer(writer);
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+    tracing_subscriber::registry()
+        .with(stdout_layer)
+        .with(file_layer)
+        .init();
+    guard
+}